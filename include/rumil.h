@@ -9,6 +9,10 @@ extern "C"
 
     Ast *parse_file(const char *filepath, bool verbose);
     void free_ast(Ast *ast);
+    bool set_diagnostic_format(const char *format);
+    bool set_dialect(const char *path);
+    bool transpile_file(const char *filepath, const char *outpath);
+    bool explain_code(const char *code);
 
 #ifdef __cplusplus
 }
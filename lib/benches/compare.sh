@@ -0,0 +1,33 @@
+#!/bin/bash
+
+# Benchmark the lexer as it was at a git ref, then compare the working tree against it.
+# Usage: benches/compare.sh <git ref> [criterion options]
+# The ref is checked out into a temporary worktree and given the current benchmarks, and its results
+# are saved as a criterion baseline named after it, so later runs can compare with --baseline too
+
+set -e
+
+if [ -z "$1" ]; then
+    echo "Usage: benches/compare.sh <git ref> [criterion options]"
+    exit 1
+fi
+
+REF="$1"
+shift
+BASELINE="$(echo "$REF" | tr -c '[:alnum:]._\n-' '_')"
+LIB_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+WORKTREE="$(mktemp -d)/rumil"
+
+# Check out the ref, with the current benchmarks and dependencies
+git -C "$LIB_DIR" worktree add --detach "$WORKTREE" "$REF"
+trap 'git -C "$LIB_DIR" worktree remove --force "$WORKTREE"' EXIT
+cp "$LIB_DIR/Cargo.toml" "$WORKTREE/lib/Cargo.toml"
+[ -f "$LIB_DIR/Cargo.lock" ] && cp "$LIB_DIR/Cargo.lock" "$WORKTREE/lib/Cargo.lock"
+mkdir -p "$WORKTREE/lib/benches"
+cp "$LIB_DIR/benches/lexer.rs" "$WORKTREE/lib/benches/lexer.rs"
+
+# Both runs share a target directory, so criterion keeps their results side by side
+cd "$WORKTREE/lib"
+CARGO_TARGET_DIR="$LIB_DIR/target" cargo bench --bench lexer -- --save-baseline "$BASELINE" "$@"
+cd "$LIB_DIR"
+cargo bench --bench lexer -- --baseline "$BASELINE" "$@"
@@ -0,0 +1,75 @@
+use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
+
+use rumil_parser::{
+    diagnostic::Diagnostics,
+    lexer::{scan, scan_lossless},
+};
+
+// A chunk of typical Rumil source, repeated to build large inputs like the ones dialect transpilers generate
+const CHUNK: &str = r#";; Sums the values, skipping any that are missing
+total := (values: [?int]) -> int {
+    sum := 0
+    i := 0
+    @ i < #values {
+        sum += values[i] ..? 0 ; missing values count as zero
+        i += 1
+    }
+    -> sum
+}
+
+greeting := `Hello, {name}! You have {count} new messages.`
+path := r"C:\Users\rumil\config.toml"
+escaped := "tab\tnewline\n \u{1f600}"
+mask := 0xff_ff & 0b1010_1010 | 0o755
+rate := 1.5e-3 * 255u8
+;* generated by the dialect transpiler ;* version 2 *; *;
+result := total([1, 2, 3]) >> 2 <<= 1 ..? -1
+größe := résumé + naïve
+"#;
+
+/// Build a source file of roughly the given size out of repeated chunks
+fn source(size: usize) -> String {
+    CHUNK.repeat(size.div_ceil(CHUNK.len()))
+}
+
+fn bench_scan(c: &mut Criterion) {
+    let file_path: String = "bench.rum".to_owned();
+    let mut group = c.benchmark_group("scan");
+    for size in [64 * 1024, 1024 * 1024] {
+        let source_code: String = source(size);
+        group.throughput(Throughput::Bytes(source_code.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size),
+            &source_code,
+            |b, source_code| {
+                b.iter(|| {
+                    let mut diagnostics: Diagnostics = Diagnostics::new();
+                    black_box(scan(source_code, &file_path, &mut diagnostics).ok());
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_scan_lossless(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scan_lossless");
+    for size in [64 * 1024, 1024 * 1024] {
+        let source_code: String = source(size);
+        group.throughput(Throughput::Bytes(source_code.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size),
+            &source_code,
+            |b, source_code| {
+                b.iter(|| {
+                    let mut diagnostics: Diagnostics = Diagnostics::new();
+                    black_box(scan_lossless(source_code, &mut diagnostics));
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_scan, bench_scan_lossless);
+criterion_main!(benches);
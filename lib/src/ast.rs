@@ -1,42 +1,560 @@
-use std::{ffi::CString, os::raw::c_char};
+use std::fmt;
 
-use crate::token::Token;
+use crate::{
+    diagnostic::Diagnostics,
+    parser::parse,
+    span::Span,
+    token::{NumberSuffix, Token},
+};
 
-#[repr(C)]
-pub struct Ast {}
+/// The root of a parsed Rumil program
+pub struct Ast {
+    pub items: Vec<Item>,
+}
 
 impl Ast {
-    /// Create a new C++ compatible AST structure on the heap
-    pub fn new(tokens: Vec<Token>) -> Box<Self> {
-        Box::new(Ast {})
+    /// Parse a token stream into a new C++ compatible AST structure on the heap. Syntax errors are added
+    /// to the diagnostics, and whatever couldn't be parsed is left in the tree as an error node
+    pub fn new(tokens: Vec<Token>, diagnostics: &mut Diagnostics) -> Box<Self> {
+        let items: Vec<Item> = parse(tokens, diagnostics);
+        Box::new(Ast { items })
     }
 }
 
-/// Free a single C string
-unsafe fn free_c_string(s: *const c_char) {
-    if s.is_null() {
-        return;
+// Items
+// -----
+
+/// A top-level item in a source file
+pub struct Item {
+    pub kind: ItemKind,
+    pub docs: Vec<String>, // lines of the doc comments written above the item, without their ;;
+    pub span: Span,
+}
+
+pub enum ItemKind {
+    /// A named function: name := (params) -> ReturnType { body }
+    Function { name: Ident, function: Function },
+
+    /// A top-level binding that isn't a function
+    Binding(Binding),
+
+    /// Any other statement, evaluated when the program runs
+    Stmt(Stmt),
+}
+
+/// A function literal: (params) -> ReturnType { body }. The return type is optional
+pub struct Function {
+    pub params: Vec<Param>,
+    pub return_type: Option<Type>,
+    pub body: Block,
+    pub span: Span,
+}
+
+/// A function parameter with an optional type annotation: name: Type
+pub struct Param {
+    pub name: Ident,
+    pub ty: Option<Type>,
+    pub span: Span,
+}
+
+/// A name along with where it was written
+pub struct Ident {
+    pub name: String,
+    pub span: Span,
+}
+
+// Statements
+// ----------
+
+/// A block of statements enclosed in braces
+pub struct Block {
+    pub statements: Vec<Stmt>,
+    pub span: Span,
+}
+
+/// A statement: anything that can appear on its own in a block or at the top level
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub span: Span,
+}
+
+pub enum StmtKind {
+    /// Declare new names with :=
+    Binding(Binding),
+
+    /// Assign to an existing place with =, <- or a compound assignment operator
+    Assign {
+        target: Expr,
+        op: AssignOp,
+        value: Expr,
+    },
+
+    /// Return from the enclosing function: -> value. The value is optional
+    Return(Option<Expr>),
+
+    /// Evaluate an expression for its side effects
+    Expr(Expr),
+}
+
+/// A declaration of new names: pattern: Type := value. The type annotation is optional
+pub struct Binding {
+    pub pattern: Pattern,
+    pub ty: Option<Type>,
+    pub value: Expr,
+    pub span: Span,
+}
+
+// Expressions
+// -----------
+
+/// An expression: anything that produces a value
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+pub enum ExprKind {
+    Identifier(String),
+    Print, // $
+    Literal(Literal),
+
+    Array(Vec<Expr>),
+    Tuple(Vec<Expr>),
+    Block(Block),
+    Function(Box<Function>),
+
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expr>,
+    },
+    Binary {
+        op: BinaryOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    Call {
+        callee: Box<Expr>,
+        args: Vec<Expr>,
+    },
+    Index {
+        target: Box<Expr>,
+        index: Box<Expr>,
+    },
+    Field {
+        target: Box<Expr>,
+        name: Ident,
+        optional: bool, // .? instead of .
+    },
+    Path {
+        target: Box<Expr>,
+        name: Ident,
+    },
+
+    /// Match a value against a list of arms: subject ? { pattern => value, ... }
+    Match {
+        subject: Box<Expr>,
+        arms: Vec<MatchArm>,
+    },
+
+    /// Loop while a condition holds, or forever without one: @ condition { body }
+    Loop {
+        condition: Option<Box<Expr>>,
+        body: Block,
+    },
+
+    /// A form string with expressions embedded in it: `text {expr} text`
+    Interpolation(Vec<InterpolationPart>),
+
+    /// Source code with a syntax error in it, standing in for the expression or statement it should have been
+    Error,
+}
+
+/// A piece of a form string
+pub enum InterpolationPart {
+    Text(String, Span), // literal text, with escape sequences decoded
+    Expr(Expr),         // an embedded expression
+}
+
+/// A single arm of a match expression: pattern => value
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub value: Expr,
+    pub span: Span,
+}
+
+/// A literal value. Quoted literals hold their contents with escape sequences decoded
+pub enum Literal {
+    Int(u128, Option<NumberSuffix>),
+    Float(f64, Option<NumberSuffix>),
+    String(String),
+    Char(char),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum UnaryOp {
+    Not,    // !
+    Negate, // -
+    BitNot, // ~
+    Length, // #
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Coalesce,        // ..?
+    Or,              // ||
+    And,             // &&
+    Equals,          // ==
+    NotEquals,       // !=
+    Matches,         // ~=
+    Less,            // <
+    LessOrEquals,    // <=
+    Greater,         // >
+    GreaterOrEquals, // >=
+    BitOr,           // |
+    BitXor,          // ^
+    BitAnd,          // &
+    LeftShift,       // <<
+    RightShift,      // >>
+    Add,             // +
+    Subtract,        // -
+    Multiply,        // *
+    Divide,          // /
+    Remainder,       // %
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum AssignOp {
+    Assign,           // =
+    Send,             // <-
+    AddAssign,        // +=
+    SubtractAssign,   // -=
+    MultiplyAssign,   // *=
+    DivideAssign,     // /=
+    RemainderAssign,  // %=
+    BitAndAssign,     // &=
+    BitXorAssign,     // ^=
+    LeftShiftAssign,  // <<=
+    RightShiftAssign, // >>=
+}
+
+// Patterns
+// --------
+
+/// A pattern that a value can be destructured into, in bindings and match arms
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub span: Span,
+}
+
+pub enum PatternKind {
+    Wildcard, // _
+    Identifier(String),
+    Literal {
+        literal: Literal,
+        negative: bool, // -1 instead of 1, for number literals
+    },
+    Tuple(Vec<Pattern>),
+    Array(Vec<Pattern>),
+}
+
+// Types
+// -----
+
+/// A type annotation
+pub struct Type {
+    pub kind: TypeKind,
+    pub span: Span,
+}
+
+pub enum TypeKind {
+    /// A named type, possibly behind a path: name::name
+    Named(Vec<Ident>),
+
+    /// An array of some type: [Type]
+    Array(Box<Type>),
+
+    /// A tuple of types: (Type, Type)
+    Tuple(Vec<Type>),
+
+    /// A function signature: (Type, Type) -> Type
+    Function { params: Vec<Type>, ret: Box<Type> },
+
+    /// A value that may be missing: ?Type
+    Optional(Box<Type>),
+}
+
+// Debug printing
+// --------------
+
+/// Allow pretty-printing of items for debugging
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for doc in &self.docs {
+            writeln!(f, ";; {}", doc)?;
+        }
+        match &self.kind {
+            ItemKind::Function { name, function } => write!(f, "{} := {}", name, function),
+            ItemKind::Binding(binding) => write!(f, "{}", binding),
+            ItemKind::Stmt(stmt) => write!(f, "{}", stmt),
+        }
     }
+}
 
-    unsafe {
-        drop(CString::from_raw(s as *mut c_char));
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}) ->", join(&self.params))?;
+        if let Some(ty) = &self.return_type {
+            write!(f, " {}", ty)?;
+        }
+        write!(f, " {}", self.body)
+    }
+}
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.ty {
+            Some(ty) => write!(f, "{}: {}", self.name, ty),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl fmt::Display for Ident {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
     }
 }
 
+impl fmt::Display for Block {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.statements.is_empty() {
+            return write!(f, "{{}}");
+        }
+        write!(f, "{{ {} }}", join(&self.statements))
+    }
+}
+
+/// Allow pretty-printing of statements for debugging
+impl fmt::Display for Stmt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            StmtKind::Binding(binding) => write!(f, "{}", binding),
+            StmtKind::Assign { target, op, value } => write!(f, "{} {} {}", target, op, value),
+            StmtKind::Return(Some(value)) => write!(f, "-> {}", value),
+            StmtKind::Return(None) => write!(f, "->"),
+            StmtKind::Expr(expr) => write!(f, "{}", expr),
+        }
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.ty {
+            Some(ty) => write!(f, "{}: {} := {}", self.pattern, ty, self.value),
+            None => write!(f, "{} := {}", self.pattern, self.value),
+        }
+    }
+}
+
+/// Allow pretty-printing of expressions for debugging. Compound expressions are fully parenthesized
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            ExprKind::Identifier(name) => write!(f, "{}", name),
+            ExprKind::Print => write!(f, "$"),
+            ExprKind::Literal(literal) => write!(f, "{}", literal),
+            ExprKind::Array(items) => write!(f, "[{}]", join(items)),
+            ExprKind::Tuple(items) if items.len() == 1 => write!(f, "({},)", items[0]),
+            ExprKind::Tuple(items) => write!(f, "({})", join(items)),
+            ExprKind::Block(block) => write!(f, "{}", block),
+            ExprKind::Function(function) => write!(f, "{}", function),
+            ExprKind::Unary { op, operand } => write!(f, "({}{})", op, operand),
+            ExprKind::Binary { op, left, right } => write!(f, "({} {} {})", left, op, right),
+            ExprKind::Call { callee, args } => write!(f, "{}({})", callee, join(args)),
+            ExprKind::Index { target, index } => write!(f, "{}[{}]", target, index),
+            ExprKind::Field {
+                target,
+                name,
+                optional,
+            } => write!(
+                f,
+                "{}{}{}",
+                target,
+                if *optional { ".?" } else { "." },
+                name
+            ),
+            ExprKind::Path { target, name } => write!(f, "{}::{}", target, name),
+            ExprKind::Match { subject, arms } => write!(f, "({} ? {{ {} }})", subject, join(arms)),
+            ExprKind::Loop {
+                condition: Some(condition),
+                body,
+            } => write!(f, "@ {} {}", condition, body),
+            ExprKind::Loop {
+                condition: None,
+                body,
+            } => write!(f, "@ {}", body),
+            ExprKind::Interpolation(parts) => {
+                let parts: String = parts.iter().map(|part| part.to_string()).collect();
+                write!(f, "`{}`", parts)
+            }
+            ExprKind::Error => write!(f, "<error>"),
+        }
+    }
+}
+
+impl fmt::Display for InterpolationPart {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InterpolationPart::Text(text, _) => {
+                let escaped: String = text.escape_debug().to_string();
+                write!(
+                    f,
+                    "{}",
+                    escaped
+                        .replace('`', "\\`")
+                        .replace('{', "\\{")
+                        .replace('}', "\\}")
+                )
+            }
+            InterpolationPart::Expr(expr) => write!(f, "{{{}}}", expr),
+        }
+    }
+}
+
+impl fmt::Display for MatchArm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} => {}", self.pattern, self.value)
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Literal::Int(value, suffix) => write!(f, "{}{}", value, suffix_name(suffix)),
+            Literal::Float(value, suffix) => write!(f, "{:?}{}", value, suffix_name(suffix)),
+            Literal::String(value) => write!(f, "\"{}\"", value.escape_debug()),
+            Literal::Char(value) => write!(f, "'{}'", value.escape_debug()),
+        }
+    }
+}
+
+impl fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            UnaryOp::Not => "!",
+            UnaryOp::Negate => "-",
+            UnaryOp::BitNot => "~",
+            UnaryOp::Length => "#",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            BinaryOp::Coalesce => "..?",
+            BinaryOp::Or => "||",
+            BinaryOp::And => "&&",
+            BinaryOp::Equals => "==",
+            BinaryOp::NotEquals => "!=",
+            BinaryOp::Matches => "~=",
+            BinaryOp::Less => "<",
+            BinaryOp::LessOrEquals => "<=",
+            BinaryOp::Greater => ">",
+            BinaryOp::GreaterOrEquals => ">=",
+            BinaryOp::BitOr => "|",
+            BinaryOp::BitXor => "^",
+            BinaryOp::BitAnd => "&",
+            BinaryOp::LeftShift => "<<",
+            BinaryOp::RightShift => ">>",
+            BinaryOp::Add => "+",
+            BinaryOp::Subtract => "-",
+            BinaryOp::Multiply => "*",
+            BinaryOp::Divide => "/",
+            BinaryOp::Remainder => "%",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+impl fmt::Display for AssignOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            AssignOp::Assign => "=",
+            AssignOp::Send => "<-",
+            AssignOp::AddAssign => "+=",
+            AssignOp::SubtractAssign => "-=",
+            AssignOp::MultiplyAssign => "*=",
+            AssignOp::DivideAssign => "/=",
+            AssignOp::RemainderAssign => "%=",
+            AssignOp::BitAndAssign => "&=",
+            AssignOp::BitXorAssign => "^=",
+            AssignOp::LeftShiftAssign => "<<=",
+            AssignOp::RightShiftAssign => ">>=",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// Allow pretty-printing of patterns for debugging
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            PatternKind::Wildcard => write!(f, "_"),
+            PatternKind::Identifier(name) => write!(f, "{}", name),
+            PatternKind::Literal { literal, negative } => {
+                write!(f, "{}{}", if *negative { "-" } else { "" }, literal)
+            }
+            PatternKind::Tuple(items) if items.len() == 1 => write!(f, "({},)", items[0]),
+            PatternKind::Tuple(items) => write!(f, "({})", join(items)),
+            PatternKind::Array(items) => write!(f, "[{}]", join(items)),
+        }
+    }
+}
+
+/// Allow pretty-printing of types for debugging
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            TypeKind::Named(path) => {
+                let names: Vec<&str> = path.iter().map(|ident| ident.name.as_str()).collect();
+                write!(f, "{}", names.join("::"))
+            }
+            TypeKind::Array(ty) => write!(f, "[{}]", ty),
+            TypeKind::Tuple(items) if items.len() == 1 => write!(f, "({},)", items[0]),
+            TypeKind::Tuple(items) => write!(f, "({})", join(items)),
+            TypeKind::Function { params, ret } => write!(f, "({}) -> {}", join(params), ret),
+            TypeKind::Optional(ty) => write!(f, "?{}", ty),
+        }
+    }
+}
+
+/// Get the text of an optional number suffix, for printing after a literal
+fn suffix_name(suffix: &Option<NumberSuffix>) -> String {
+    suffix.map_or(String::new(), |suffix| suffix.to_string())
+}
+
+/// Join displayable nodes into a comma separated list
+fn join<T: fmt::Display>(nodes: &[T]) -> String {
+    nodes
+        .iter()
+        .map(|node| node.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
 /// Free the entire AST and all its heap-allocated resources
+///
+/// # Safety
+/// ast must be null or a pointer returned by parse_file that hasn't been freed yet
 #[unsafe(no_mangle)]
-pub extern "C" fn free_ast(ast: *mut Ast) {
+pub unsafe extern "C" fn free_ast(ast: *mut Ast) {
     if ast.is_null() {
         return;
     }
 
     unsafe {
-        let owned_ast: Box<Ast> = Box::from_raw(ast);
-
-        // Free fields
-        // free_c_string(owned_ast.some_str), etc...
-
-        // Free AST
-        drop(owned_ast);
+        drop(Box::from_raw(ast));
     }
 }
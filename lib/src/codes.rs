@@ -0,0 +1,480 @@
+// Stable codes for every kind of problem the parser reports. Lexer problems are numbered from R0001,
+// and parser problems from R0101. Codes are never reused or renumbered, so they can be searched for
+// and referenced from editors and CI logs.
+
+// Lexer codes
+// -----------
+
+pub const UNCLOSED_QUOTE: &str = "R0001";
+pub const INVALID_CHAR_LITERAL: &str = "R0002";
+pub const UNRECOGNIZED_CHARACTER: &str = "R0003";
+pub const INVALID_ESCAPE: &str = "R0004";
+pub const MALFORMED_UNICODE_ESCAPE: &str = "R0005";
+pub const INVALID_CODE_POINT: &str = "R0006";
+pub const UNCLOSED_INTERPOLATION: &str = "R0007";
+pub const MALFORMED_NUMBER: &str = "R0008";
+pub const INVALID_DIGIT: &str = "R0009";
+pub const INVALID_NUMBER_SUFFIX: &str = "R0010";
+pub const NUMBER_OUT_OF_RANGE: &str = "R0011";
+pub const MIXED_SCRIPT_IDENTIFIER: &str = "R0012";
+pub const CONFUSABLE_IDENTIFIER: &str = "R0013";
+pub const MIXED_DIGIT_SYSTEMS: &str = "R0014";
+pub const UNCLOSED_BLOCK_COMMENT: &str = "R0015";
+
+// Parser codes
+// ------------
+
+pub const EXPECTED_TOKEN: &str = "R0101";
+pub const EXPECTED_EXPRESSION: &str = "R0102";
+pub const EXPECTED_PATTERN: &str = "R0103";
+pub const EXPECTED_TYPE: &str = "R0104";
+pub const UNCLOSED_BLOCK: &str = "R0105";
+pub const UNCLOSED_MATCH: &str = "R0106";
+pub const MISSING_ARM_SEPARATOR: &str = "R0107";
+pub const INVALID_ASSIGNMENT: &str = "R0108";
+pub const UNATTACHED_DOC_COMMENT: &str = "R0109";
+
+/// A registered error code along with its long-form explanation
+pub struct ErrorCode {
+    pub code: &'static str,
+    pub title: &'static str,       // one line summary of the problem
+    pub explanation: &'static str, // what causes the problem and how to fix it, with examples
+}
+
+static REGISTRY: &[ErrorCode] = &[
+    ErrorCode {
+        code: UNCLOSED_QUOTE,
+        title: "A string, char or form string literal is missing its closing quote",
+        explanation: r##"A quote opens a literal that runs until the matching closing quote. If the end of
+the file is reached first, the literal is unclosed.
+
+Erroneous example:
+
+    greeting := "Hello, world!
+
+Add the closing quote:
+
+    greeting := "Hello, world!"
+
+A quote preceded by a backslash is escaped and does not close the literal, so
+make sure the last quote isn't escaped by accident:
+
+    path := "C:\folder\"    ; the final quote is escaped
+    path := "C:\\folder\\"  ; escape the backslashes instead
+    path := r"C:\folder\"    ; or use a raw string, which has no escapes
+
+A raw string ends at the first quote followed by as many #s as it started with:
+
+    quote := r#"say "hi""#    ; "hi" doesn't close the string
+    quote := r#"say "hi"      ; but nothing closes it here"##,
+    },
+    ErrorCode {
+        code: INVALID_CHAR_LITERAL,
+        title: "A char literal does not hold exactly one char",
+        explanation: r#"Single quotes make a char literal, which holds exactly one char. That can be a
+single char, an escape sequence such as \n or \', or a \u code point: either
+exactly four hex digits, as in \u00e9, or one to six hex digits in braces, as
+in \u{1f600}.
+
+Erroneous examples:
+
+    c := 'ab'
+    c := ''
+
+Use a string for more than one char, or a single char for a char literal:
+
+    s := "ab"
+    c := 'a'
+    c := '\u00e9'
+    c := '\u{1f600}'"#,
+    },
+    ErrorCode {
+        code: UNRECOGNIZED_CHARACTER,
+        title: "The source code contains a character that isn't part of Rumil's syntax",
+        explanation: r#"Every character outside of literals and comments must belong to an identifier, a
+number, an operator or whitespace.
+
+Erroneous example:
+
+    price := 1 £ 2
+
+Remove the character, or put it inside a string or comment:
+
+    price := "1 £ 2"  ; a price in pounds"#,
+    },
+    ErrorCode {
+        code: INVALID_ESCAPE,
+        title: "A backslash in a literal is followed by a char that can't be escaped",
+        explanation: r#"Inside strings, chars and form strings, a backslash starts an escape sequence.
+The valid escapes are:
+
+    \\  backslash          \n  newline
+    \'  single quote       \r  carriage return
+    \"  double quote       \t  tab
+    \`  backtick           \0  null char
+    \a  alert              \v  vertical tab
+    \b  backspace          \f  form feed
+    \u  unicode code point, such as \u00e9 or \u{1f600}
+
+Erroneous example:
+
+    path := "C:\folder"
+
+Escape the backslash to write it literally:
+
+    path := "C:\\folder""#,
+    },
+    ErrorCode {
+        code: MALFORMED_UNICODE_ESCAPE,
+        title: "A \\u escape is not followed by a valid code point",
+        explanation: r#"A unicode escape is \u followed by exactly 4 hex digits, or by 1 to 6 hex digits
+in braces.
+
+Erroneous examples:
+
+    e := '\u0e9'
+    smile := "\u{1f600"
+
+Write 4 digits, or close the braces:
+
+    e := '\u00e9'
+    smile := "\u{1f600}""#,
+    },
+    ErrorCode {
+        code: INVALID_CODE_POINT,
+        title: "A \\u escape names a code point that isn't a char",
+        explanation: r#"Chars are unicode scalar values: U+0000 to U+D7FF and U+E000 to U+10FFFF. The
+surrogates U+D800 to U+DFFF are only used in pairs by UTF-16 and aren't chars on
+their own, and there are no code points past U+10FFFF.
+
+Erroneous examples:
+
+    c := '\ud83d'
+    c := '\u{110000}'
+
+Escape the code point of the char itself, rather than its UTF-16 surrogates:
+
+    c := '\u{1f600}'"#,
+    },
+    ErrorCode {
+        code: UNCLOSED_INTERPOLATION,
+        title: "An expression embedded in a form string is missing its closing brace",
+        explanation: r#"In a form string, a { starts an embedded expression that runs until the matching
+}. If the end of the file is reached first, the interpolation is unclosed.
+
+Erroneous example:
+
+    greeting := `Hello, {name!`
+
+Close the interpolation:
+
+    greeting := `Hello, {name}!`
+
+Write \{ and \} for literal braces:
+
+    set := `\{1, 2, 3\}`"#,
+    },
+    ErrorCode {
+        code: MALFORMED_NUMBER,
+        title: "A number literal is missing some of its digits",
+        explanation: r#"A 0x, 0o or 0b prefix must be followed by at least one digit, and an exponent
+must be followed by the power of ten.
+
+Erroneous examples:
+
+    mask := 0x
+    tiny := 1.5e
+
+Add the missing digits:
+
+    mask := 0xff
+    tiny := 1.5e-3"#,
+    },
+    ErrorCode {
+        code: INVALID_DIGIT,
+        title: "A number literal uses a digit that doesn't belong to its base",
+        explanation: r#"Binary literals (0b) only use the digits 0 and 1, octal literals (0o) use 0 to 7,
+and hexadecimal literals (0x) use 0 to 9 and a to f.
+
+Erroneous examples:
+
+    flags := 0b102
+    mode := 0o789
+
+Use digits from the base, or a different base:
+
+    flags := 0b101
+    mode := 0o755"#,
+    },
+    ErrorCode {
+        code: INVALID_NUMBER_SUFFIX,
+        title: "A number literal has a suffix that isn't a valid type",
+        explanation: r#"A number literal can end with a type suffix: i8, i16, i32, i64, i128, u8, u16,
+u32, u64, u128, f32 or f64. Float suffixes only go on decimal literals, and
+integer suffixes can't go on literals with a fraction or exponent.
+
+Erroneous examples:
+
+    byte := 255byte
+    half := 0.5i32
+    x := 1abc
+
+Use a valid suffix, or separate the number from the name after it:
+
+    byte := 255u8
+    half := 0.5f32
+    x := 1 * abc"#,
+    },
+    ErrorCode {
+        code: NUMBER_OUT_OF_RANGE,
+        title: "A number literal is too large for its type",
+        explanation: r#"An integer literal must fit in its suffix type, or in u128 if it has none. Since
+the minus sign is an operator, signed suffixes allow one past the type's maximum
+so that the smallest value can be written, as in -128i8. A float literal must be
+finite in its type.
+
+Erroneous examples:
+
+    byte := 256u8
+    huge := 1e400
+
+Use a larger type, or a smaller value:
+
+    word := 256u16
+    huge := 1e300"#,
+    },
+    ErrorCode {
+        code: MIXED_SCRIPT_IDENTIFIER,
+        title: "An identifier mixes letters from different scripts (warning)",
+        explanation: r#"Identifiers can be written in any script, such as Latin, Greek, Cyrillic,
+Devanagari, Arabic or Han. Mixing scripts in one identifier is allowed, but it
+is usually a mistake, and can hide a name that looks like another one.
+
+Example:
+
+    pаth := "/tmp"    ; the a is Cyrillic
+
+Write the identifier in a single script:
+
+    path := "/tmp"
+    путь := "/tmp""#,
+    },
+    ErrorCode {
+        code: CONFUSABLE_IDENTIFIER,
+        title: "Two identifiers look the same but are different (warning)",
+        explanation: r#"Some chars from different scripts look alike, such as the Latin o and the Greek
+ο. Two identifiers made of such chars are different names that can't be told
+apart when reading the code. This is only reported when one of the identifiers
+uses chars outside of ASCII.
+
+Example:
+
+    scope := 1
+    ѕcope := 2    ; the s is Cyrillic
+
+Rename one of the identifiers so they look different:
+
+    scope := 1
+    inner_scope := 2"#,
+    },
+    ErrorCode {
+        code: MIXED_DIGIT_SYSTEMS,
+        title: "A number literal mixes digits from different scripts",
+        explanation: r#"Decimal number literals can be written with the digits of any script, such as
+Arabic-Indic, Devanagari or fullwidth digits, but every digit in one literal
+must come from the same script.
+
+Erroneous example:
+
+    total := 12३
+
+Write the whole number in one script:
+
+    total := 123
+    total := १२३
+
+Hexadecimal, octal and binary literals only use ASCII digits."#,
+    },
+    ErrorCode {
+        code: UNCLOSED_BLOCK_COMMENT,
+        title: "A block comment is never closed",
+        explanation: r#"Block comments start with ;* and end with *;. They can be nested, so every ;*
+inside a block comment needs a *; of its own.
+
+Erroneous example:
+
+    ;* Scale the total ;* by the rate *;
+    total := total * rate
+
+Close every block comment:
+
+    ;* Scale the total ;* by the rate *; *;
+    total := total * rate"#,
+    },
+    ErrorCode {
+        code: EXPECTED_TOKEN,
+        title: "A specific token was expected but something else was found",
+        explanation: r#"Some parts of the syntax need a particular token to come next, such as the closing
+bracket of a list or the => of a match arm.
+
+Erroneous example:
+
+    values := [1, 2, 3
+
+Add the missing token:
+
+    values := [1, 2, 3]"#,
+    },
+    ErrorCode {
+        code: EXPECTED_EXPRESSION,
+        title: "An expression was expected but something else was found",
+        explanation: r#"Operators, bindings, assignments and returns all need an expression as their
+operand or value.
+
+Erroneous examples:
+
+    total := 1 +
+    x :=
+
+Complete the expression:
+
+    total := 1 + 2
+    x := 0"#,
+    },
+    ErrorCode {
+        code: EXPECTED_PATTERN,
+        title: "A pattern was expected but something else was found",
+        explanation: r#"Match arms and destructuring bindings start with a pattern. A pattern is _, a
+name, a literal, or a tuple or list of patterns.
+
+Erroneous example:
+
+    n ? {
+        + => "positive"
+    }
+
+Use a valid pattern:
+
+    n ? {
+        -1 => "minus one"
+        0 => "zero"
+        _ => "something else"
+    }"#,
+    },
+    ErrorCode {
+        code: EXPECTED_TYPE,
+        title: "A type was expected but something else was found",
+        explanation: r#"Type annotations follow a : in bindings and parameters, and a -> in function
+signatures. A type is a name such as int, a list type [T], a tuple type (A, B),
+a function type (A) -> B or an optional type ?T.
+
+Erroneous example:
+
+    x: := 1
+
+Add the type, or remove the colon to let it be inferred:
+
+    x: int := 1
+    x := 1"#,
+    },
+    ErrorCode {
+        code: UNCLOSED_BLOCK,
+        title: "A block is missing its closing brace",
+        explanation: r#"A { opens a block of statements that must be closed with a matching }. The error
+points at the end of the file, and a label points at the brace that was never
+closed.
+
+Erroneous example:
+
+    main := () -> {
+        $("Hello, world!")
+
+Close the block:
+
+    main := () -> {
+        $("Hello, world!")
+    }"#,
+    },
+    ErrorCode {
+        code: UNCLOSED_MATCH,
+        title: "A match is missing its closing brace",
+        explanation: r#"The arms of a match are wrapped in braces. The error points at the end of the
+file, and a label points at the brace that was never closed.
+
+Erroneous example:
+
+    name := n ? {
+        0 => "zero"
+        _ => "other"
+
+Close the match:
+
+    name := n ? {
+        0 => "zero"
+        _ => "other"
+    }"#,
+    },
+    ErrorCode {
+        code: MISSING_ARM_SEPARATOR,
+        title: "Two match arms are not separated",
+        explanation: r#"Match arms are separated by commas or new lines.
+
+Erroneous example:
+
+    name := n ? { 0 => "zero" _ => "other" }
+
+Separate the arms:
+
+    name := n ? { 0 => "zero", _ => "other" }"#,
+    },
+    ErrorCode {
+        code: INVALID_ASSIGNMENT,
+        title: "The left side of an assignment can't be assigned to",
+        explanation: r#"Only names, fields, indexes and paths can be assigned to with = or a compound
+assignment operator such as +=.
+
+Erroneous examples:
+
+    1 = x
+    f() += 1
+
+Assign to a place instead:
+
+    x = 1
+    values[0] += 1"#,
+    },
+    ErrorCode {
+        code: UNATTACHED_DOC_COMMENT,
+        title: "A doc comment doesn't document anything",
+        explanation: r#"This is a warning. Doc comments start with ;; and document the top-level
+binding, function or statement that comes right after them. A doc comment
+anywhere else, such as inside a function body or at the end of a file, isn't
+attached to anything and won't show up in generated documentation.
+
+Example:
+
+    add := (a: int, b: int) -> int {
+        ;; Sum the arguments
+        -> a + b
+    }
+
+Move the doc comment above the item, or use a ; comment instead:
+
+    ;; Sum the arguments
+    add := (a: int, b: int) -> int {
+        -> a + b
+    }"#,
+    },
+];
+
+/// Get every registered code, in order
+pub fn registry() -> &'static [ErrorCode] {
+    REGISTRY
+}
+
+/// Get the registered entry for a code, ignoring case so that r0001 finds R0001
+pub fn explain(code: &str) -> Option<&'static ErrorCode> {
+    REGISTRY
+        .iter()
+        .find(|entry| entry.code.eq_ignore_ascii_case(code.trim()))
+}
@@ -0,0 +1,168 @@
+use serde::Serialize;
+
+use crate::{
+    log::{render_diagnostic, render_json},
+    span::Span,
+};
+
+/// How serious a diagnostic is
+#[derive(strum_macros::Display, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+/// A secondary span of source code that helps explain a diagnostic
+#[derive(Serialize, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+/// A problem found in the source code, along with everything needed to explain it
+#[derive(Serialize, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: Option<String>, // stable identifier for this kind of problem
+    pub message: String,
+    pub span: Span,           // where the problem is
+    pub labels: Vec<Label>,   // other places that are relevant to the problem
+    pub notes: Vec<String>,   // extra context about the problem
+    pub help: Option<String>, // how to fix the problem
+}
+
+impl Diagnostic {
+    /// Create a new Diagnostic
+    pub fn new(severity: Severity, message: String, span: Span) -> Diagnostic {
+        Diagnostic {
+            severity,
+            code: None,
+            message,
+            span,
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: None,
+        }
+    }
+
+    /// Create a new error Diagnostic
+    pub fn error(message: String, span: Span) -> Diagnostic {
+        Diagnostic::new(Severity::Error, message, span)
+    }
+
+    /// Create a new warning Diagnostic
+    pub fn warning(message: String, span: Span) -> Diagnostic {
+        Diagnostic::new(Severity::Warning, message, span)
+    }
+
+    /// Set the code of this Diagnostic
+    pub fn with_code(mut self, code: &str) -> Diagnostic {
+        self.code = Some(code.to_owned());
+        self
+    }
+
+    /// Add a secondary label to this Diagnostic
+    pub fn with_label(mut self, span: Span, message: String) -> Diagnostic {
+        self.labels.push(Label { span, message });
+        self
+    }
+
+    /// Add a note to this Diagnostic
+    pub fn with_note(mut self, note: String) -> Diagnostic {
+        self.notes.push(note);
+        self
+    }
+
+    /// Set the help text of this Diagnostic
+    pub fn with_help(mut self, help: String) -> Diagnostic {
+        self.help = Some(help);
+        self
+    }
+
+    /// Render this Diagnostic the way it's printed for people to read: a header, its location, and the
+    /// source lines it covers with its spans underlined. Colors are only added when asked for
+    pub fn render(&self, source_code: &str, file_path: &str, color: bool) -> String {
+        render_diagnostic(self, source_code, file_path, color)
+    }
+
+    /// Render this Diagnostic as a single line of JSON, along with the file it was found in
+    pub fn to_json(&self, file_path: &str) -> String {
+        render_json(self, file_path)
+    }
+}
+
+/// A sink that collects the diagnostics from every compiler pass
+#[derive(Default, Clone)]
+pub struct Diagnostics {
+    items: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    /// Create a new, empty Diagnostics sink
+    pub fn new() -> Diagnostics {
+        Diagnostics { items: Vec::new() }
+    }
+
+    /// Add a diagnostic to the sink
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.items.push(diagnostic);
+    }
+
+    /// Move every diagnostic from another sink into this one
+    pub fn append(&mut self, other: &mut Diagnostics) {
+        self.items.append(&mut other.items);
+    }
+
+    /// Keep only the diagnostics that pass a test, such as the ones outside source code that was scanned again
+    pub fn retain(&mut self, keep: impl FnMut(&Diagnostic) -> bool) {
+        self.items.retain(keep);
+    }
+
+    /// Drop every diagnostic after the first len, such as ones found while scanning ahead and backing out
+    pub fn truncate(&mut self, len: usize) {
+        self.items.truncate(len);
+    }
+
+    /// Iterate over the collected diagnostics
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.items.iter()
+    }
+
+    /// Iterate over the collected diagnostics, to change them in place
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Diagnostic> {
+        self.items.iter_mut()
+    }
+
+    /// Iterate over the collected diagnostics of a given severity
+    pub fn with_severity(&self, severity: Severity) -> impl Iterator<Item = &Diagnostic> {
+        self.items.iter().filter(move |d| d.severity == severity)
+    }
+
+    /// Count the collected diagnostics of a given severity
+    pub fn count(&self, severity: Severity) -> usize {
+        self.with_severity(severity).count()
+    }
+
+    /// Check if any errors have been collected
+    pub fn has_errors(&self) -> bool {
+        self.items.iter().any(|d| d.severity == Severity::Error)
+    }
+
+    /// Get the number of collected diagnostics
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Check if no diagnostics have been collected
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Sort the diagnostics by where they appear in the source code, so they read from top to bottom.
+    /// The sort is stable, so diagnostics at the same place keep the order they were found in
+    pub fn sort(&mut self) {
+        self.items.sort_by_key(|d| d.span.start.offset);
+    }
+}
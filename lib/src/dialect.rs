@@ -0,0 +1,158 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::read_to_string,
+    path::Path,
+};
+
+use serde::Deserialize;
+use unicode_normalization::{UnicodeNormalization, is_nfc};
+use unicode_xid::UnicodeXID;
+
+use crate::{
+    operators::{OPERATORS, Operators, STANDARD},
+    token::TokenType,
+};
+
+/// A natural-language surface syntax for Rumil. A dialect maps its own keywords and symbols onto Rumil's
+/// operators, so source code written in it lexes straight into the standard Rumil token stream
+pub struct Dialect {
+    pub name: String,                     // what the dialect is called, such as Español
+    keywords: HashMap<String, TokenType>, // words that scan as operators, in NFC
+    operators: Operators,                 // the standard operators, plus the dialect's own symbols
+    spellings: Vec<(TokenType, String)>, // how to write each operator the dialect has its own word or symbol for
+}
+
+/// A dialect as it's written in a TOML or JSON file. Each keyword and operator maps to a Rumil operator,
+/// written either as its symbol, like "!=", or as its token type, like "BangEquals"
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DialectFile {
+    name: String,
+    #[serde(default)]
+    keywords: BTreeMap<String, String>, // sorted, so problems are always reported in the same order
+    #[serde(default)]
+    operators: BTreeMap<String, String>,
+}
+
+impl Dialect {
+    /// Load a dialect from a .toml or .json file
+    pub fn load(path: &Path) -> Result<Dialect, String> {
+        let text: String = read_to_string(path)
+            .map_err(|msg| format!("Error reading {}: {}", path.display(), msg))?;
+
+        let dialect: Result<Dialect, String> = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Dialect::from_toml(&text),
+            Some("json") => Dialect::from_json(&text),
+            _ => {
+                return Err(format!(
+                    "Dialect {} isn't a .toml or .json file",
+                    path.display()
+                ));
+            }
+        };
+        dialect.map_err(|msg| format!("{} in {}", msg, path.display()))
+    }
+
+    /// Read a dialect from the text of a TOML file
+    pub fn from_toml(text: &str) -> Result<Dialect, String> {
+        let file: DialectFile = toml::from_str(text).map_err(|err| {
+            let line: usize = err
+                .span()
+                .map_or(1, |span| text[..span.start].lines().count().max(1));
+            format!("Invalid dialect: {} at line {}", err.message(), line)
+        })?;
+        Dialect::compile(file)
+    }
+
+    /// Read a dialect from the text of a JSON file
+    pub fn from_json(text: &str) -> Result<Dialect, String> {
+        let file: DialectFile =
+            serde_json::from_str(text).map_err(|err| format!("Invalid dialect: {}", err))?;
+        Dialect::compile(file)
+    }
+
+    /// Get the operator a word stands for, if it's one of the dialect's keywords
+    pub fn keyword(&self, word: &str) -> Option<TokenType> {
+        if is_nfc(word) {
+            self.keywords.get(word).copied()
+        } else {
+            self.keywords.get(&word.nfc().collect::<String>()).copied()
+        }
+    }
+
+    /// Get how this dialect writes an operator, if it has its own word or symbol for it. Keywords are
+    /// preferred over symbols, and the first in alphabetical order is taken when there are several
+    pub fn spelling(&self, token_type: TokenType) -> Option<&str> {
+        self.spellings
+            .iter()
+            .find(|(spelled, _)| *spelled == token_type)
+            .map(|(_, spelling)| spelling.as_str())
+    }
+
+    /// Get the operators source code in this dialect can use
+    pub fn operators(&self) -> &Operators {
+        &self.operators
+    }
+
+    /// Check a dialect file's mappings and build the dialect from them
+    fn compile(file: DialectFile) -> Result<Dialect, String> {
+        let mut keywords: HashMap<String, TokenType> = HashMap::new();
+        let mut spellings: Vec<(TokenType, String)> = Vec::new();
+        for (word, target) in &file.keywords {
+            if !is_keyword(word) {
+                return Err(format!(
+                    "Keyword [{}] has to be spelled like an identifier",
+                    word
+                ));
+            }
+            let word: String = word.nfc().collect();
+            let token_type: TokenType = resolve(target)?;
+            keywords.insert(word.clone(), token_type);
+            add_spelling(&mut spellings, token_type, word);
+        }
+
+        let mut operators: Operators = Operators::standard();
+        for (symbol, target) in &file.operators {
+            let token_type: TokenType = resolve(target)?;
+            operators.register(symbol, token_type)?;
+            add_spelling(&mut spellings, token_type, symbol.clone());
+        }
+
+        Ok(Dialect {
+            name: file.name,
+            keywords,
+            operators,
+            spellings,
+        })
+    }
+}
+
+/// Utility function to find the Rumil operator a dialect maps something onto, given its symbol or its token type
+fn resolve(target: &str) -> Result<TokenType, String> {
+    STANDARD
+        .get(target)
+        .or_else(|| {
+            OPERATORS
+                .iter()
+                .find(|operator| operator.token_type.to_string() == target)
+                .map(|operator| operator.token_type)
+        })
+        .ok_or_else(|| format!("[{}] isn't a Rumil operator", target))
+}
+
+/// Utility function to keep the first spelling found for each operator
+fn add_spelling(spellings: &mut Vec<(TokenType, String)>, token_type: TokenType, spelling: String) {
+    if !spellings.iter().any(|(spelled, _)| *spelled == token_type) {
+        spellings.push((token_type, spelling));
+    }
+}
+
+/// Utility function to tell us if a word would scan as a single identifier, which a keyword has to
+fn is_keyword(word: &str) -> bool {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some('_') => word.len() > 1 && chars.all(|c| c.is_xid_continue()),
+        Some(c) => c.is_xid_start() && chars.all(|c| c.is_xid_continue()),
+        None => false,
+    }
+}
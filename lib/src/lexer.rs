@@ -1,294 +1,1158 @@
-use std::vec::Vec;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet, VecDeque},
+    ops::Range,
+    vec::Vec,
+};
+
+use unicode_script::{Script, UnicodeScript};
+use unicode_security::{MixedScript, skeleton};
+use unicode_xid::UnicodeXID;
 
 use crate::{
-    log::{debugging, log_debug, log_error},
-    token::{Token, TokenType, parse_op},
+    codes,
+    diagnostic::{Diagnostic, Diagnostics},
+    dialect::Dialect,
+    log::{debugging, log_debug},
+    numerals::{digit_system_name, digit_value, digit_zero},
+    operators::{Operators, STANDARD},
+    span::{Position, Span},
+    token::{FormSegment, LiteralValue, NumberSuffix, Token, TokenType, Trivia, TriviaKind},
 };
 
-struct Lexer {
-    input: Vec<char>, // split input source code into individual chars
-    pos: usize,       // current scan position
-    next: usize,      // next scan position
-    cur: char,        // the char we're currently looking at
-    line: i32,        // what line in the source code we're at
-    col: i32,         // what column in the source code we're at
-    error_count: i32, // how many scan errors we've had
+/// Scans tokens from source code on demand. A Lexer is an Iterator over the tokens of the source code it
+/// borrows, and can peek any number of tokens ahead. It stops before the EOF token, unless it's lossless:
+/// then the EOF token comes last, since it holds the trivia after the last token
+pub struct Lexer<'a> {
+    input: &'a str,  // the source code being scanned, which tokens borrow their text from
+    pos: usize,      // byte index of the current char
+    next: usize,     // byte index of the char after the current one
+    cur: char,       // the char we're currently looking at
+    line: i32,       // what line in the source code we're at
+    col: i32,        // what column in the source code we're at
+    start: Position, // where the token currently being scanned starts
+    diagnostics: Diagnostics, // problems found while scanning
+    skeletons: HashMap<String, (Cow<'a, str>, Span)>, // first identifier seen with each confusable skeleton
+    identifiers: HashSet<Cow<'a, str>>,               // every identifier seen so far
+    lossless: bool, // keep whitespace and comments as trivia on the tokens around them
+    lookahead: VecDeque<Token<'a>>, // tokens scanned by peeking, but not yet taken
+    reach: usize,   // the furthest byte the current token's scan read before backing up, if it did
+    read_past: Vec<Range<usize>>, // each token whose scan read past its end, up to the furthest byte read
+    finished: bool,               // whether the EOF token has been scanned
+    operators: &'a Operators,     // the operators to recognize, which dialects can add to
+    dialect: Option<&'a Dialect>, // the dialect the source code is written in, if it isn't plain Rumil
 }
 
-impl Lexer {
+impl<'a> Lexer<'a> {
     /// Create a new Lexer
-    fn new(source_code: String) -> Lexer {
+    pub fn new(source_code: &'a str) -> Lexer<'a> {
+        Lexer::with_mode(source_code, false)
+    }
+
+    /// Create a new lossless Lexer, which attaches whitespace and comments to tokens as trivia
+    pub fn lossless(source_code: &'a str) -> Lexer<'a> {
+        Lexer::with_mode(source_code, true)
+    }
+
+    /// Recognize the given operators instead of the standard ones, such as a dialect's extended set
+    pub fn with_operators(mut self, operators: &'a Operators) -> Lexer<'a> {
+        self.operators = operators;
+        self
+    }
+
+    /// Scan source code written in a dialect: its keywords and symbols scan as the Rumil operators they
+    /// stand for, while each token's value keeps the dialect's spelling
+    pub fn with_dialect(mut self, dialect: &'a Dialect) -> Lexer<'a> {
+        self.operators = dialect.operators();
+        self.dialect = Some(dialect);
+        self
+    }
+
+    /// Start scanning partway through the source code, at a position where a token or trivia starts,
+    /// such as the first token that has to be scanned again after an edit
+    pub(crate) fn starting_at(mut self, position: Position) -> Lexer<'a> {
+        self.pos = position.offset;
+        self.line = position.line;
+        self.col = position.col;
+        self.cur = self.char_at(self.pos);
+        self.next = self.pos + self.cur.len_utf8();
+        self
+    }
+
+    /// Create a new Lexer, choosing whether it's lossless
+    fn with_mode(source_code: &'a str, lossless: bool) -> Lexer<'a> {
         let mut lexer = Lexer {
-            input: source_code.chars().collect(),
+            input: source_code,
             pos: 0,
             next: 0,
             cur: '\u{0}',
             line: 1,
-            col: 0,
-            error_count: 0,
+            col: 1,
+            start: Position::default(),
+            diagnostics: Diagnostics::new(),
+            skeletons: HashMap::new(),
+            identifiers: HashSet::new(),
+            lossless,
+            lookahead: VecDeque::new(),
+            reach: 0,
+            read_past: Vec::new(),
+            finished: false,
+            operators: &STANDARD,
+            dialect: None,
         };
 
-        lexer.read_char();
+        lexer.cur = lexer.char_at(0);
+        lexer.next = lexer.cur.len_utf8();
         lexer
     }
 
-    /// Get the next Token
-    fn next_token(&mut self) -> Token {
-        let empty_token = Token::new("".to_owned(), TokenType::EOF, self.line, self.col);
-        self.skip_whitespace();
+    /// Look at the next token without taking it
+    pub fn peek(&mut self) -> Option<&Token<'a>> {
+        self.peek_nth(0)
+    }
+
+    /// Look at the token n places ahead without taking any, so that peek_nth(0) is the next token
+    pub fn peek_nth(&mut self, n: usize) -> Option<&Token<'a>> {
+        while self.lookahead.len() <= n && !self.finished {
+            self.reach = 0;
+            let token: Token<'a> = self.next_token();
+            if self.reach > token.span.end.offset {
+                self.read_past.push(token.span.start.offset..self.reach);
+            }
+            if token.token_type == TokenType::EOF {
+                self.finished = true;
+                if !self.lossless {
+                    break;
+                }
+            }
+            self.lookahead.push_back(token);
+        }
+
+        self.lookahead.get(n)
+    }
+
+    /// Take the problems found so far. Peeking scans ahead, so this includes problems in peeked tokens
+    pub fn take_diagnostics(&mut self) -> Diagnostics {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /// Take the tokens scanned so far whose scan read past their own end, each as the range from the
+    /// token's start to the furthest byte its scan read. Reading one char past a token to see where it ends
+    /// doesn't count; this is for scans that back up, like an interpolation that finds its string's closing
+    /// quote, since the text they read decides how the token scans without being part of it
+    pub(crate) fn take_read_past(&mut self) -> Vec<Range<usize>> {
+        std::mem::take(&mut self.read_past)
+    }
+
+    /// Scan the next Token
+    fn next_token(&mut self) -> Token<'a> {
+        if !self.lossless {
+            self.skip_whitespace();
+            return self.scan_token();
+        }
+
+        // Trivia on a line with a token trails it; anything on lines of its own leads the next token
+        let leading: Vec<Trivia<'a>> = self.read_trivia(false);
+        let mut token: Token<'a> = self.scan_token();
+        token.leading = leading;
+        if token.token_type != TokenType::EOF {
+            token.trailing = self.read_trivia(true);
+        }
+        token
+    }
+
+    /// Scan the Token starting at the current char
+    fn scan_token(&mut self) -> Token<'a> {
+        self.start = self.location();
 
         // Reached end of input, return the empty token
-        if self.cur == '\u{0}' {
-            return empty_token;
+        if self.at_end() {
+            return self.create_token(TokenType::EOF, "");
         }
 
         // Scan tokens based on the type of initial char
         // ---------------------------------------------
 
-        if is_letter(self.cur) || (self.cur == '_' && self.next < self.input.len() && is_alnum(self.input[self.next])) {
-            let value = self.read_identifier();
-            return self.create_token(TokenType::Identifier, value);
+        // Raw strings start with a letter, so they have to be checked for before identifiers
+        if self.at_raw_string() {
+            return self.read_raw_string();
+        }
+
+        if is_letter(self.cur) || (self.cur == '_' && is_alnum(self.peek_char())) {
+            let value: &'a str = self.read_identifier();
+            if let Some(token_type) = self.dialect.and_then(|dialect| dialect.keyword(value)) {
+                return self.create_token(token_type, value);
+            }
+            let token: Token<'a> = self.create_token(TokenType::Identifier, value);
+            self.check_identifier(&token);
+            return token;
         }
 
         if is_digit(self.cur) {
-            let (token_type, value) = self.read_number();
-            return self.create_token(token_type, value);
+            return self.read_number();
         }
 
         if is_quote(self.cur) {
-            let (token_type, value, num_lines) = self.read_quote();
-            let qt: Token = self.create_token(token_type, value);
-
-            // Adjust the line and column pointers for the number of lines that were scanned for this quote
-            if num_lines > 0 {
-                self.line += num_lines;
-                self.col = 0;
-            }
-
-            return qt;
+            return self.read_quote();
         }
 
-        match parse_op(self.cur.to_string().as_str()) {
-            Some(op) => {
-                let (token_type, value) = self.read_operator(op);
-                return self.create_token(token_type, value);
-            }
-            None => {}
+        if let Some((token_type, len)) = self.operators.longest_match(&self.input[self.pos..]) {
+            let value: &'a str = self.read_operator(len);
+            return self.create_token(token_type, value);
         }
 
         // comment opening
         if self.cur == ';' {
-            let value = self.read_comment();
-            return self.create_token(TokenType::Comment, value);
+            let (token_type, value) = self.read_comment();
+            return self.create_token(token_type, value);
         }
 
-        log_error(format!(
-            "Unrecognized character [{}] on line {} col {}",
-            self.cur, self.line, self.col
-        ));
-        self.error_count += 1;
+        // Anything else is an error, but we can keep scanning from the next char
+        let start: usize = self.pos;
         self.read_char();
-        empty_token
+        let value: &'a str = self.text_from(start);
+        self.create_error(
+            codes::UNRECOGNIZED_CHARACTER,
+            value,
+            format!("Unrecognized character [{}]", value),
+            None,
+        )
     }
 
-    /// Create a token with the given parameters
-    fn create_token(&mut self, token_type: TokenType, value: String) -> Token {
-        // If we're creating a token enclosed in quotes, subtract the quote positions from the token's location
-        let mut col_offs = (&value).len() as i32;
-        if token_type == TokenType::String
-            || token_type == TokenType::FormString
-            || token_type == TokenType::Char
-        {
-            col_offs += 2;
+    /// Create a token with the given parameters, spanning from where it started up to the current char
+    fn create_token(&mut self, token_type: TokenType, value: &'a str) -> Token<'a> {
+        Token::new(value, token_type, Span::new(self.start, self.location()))
+    }
+
+    /// Create an error token covering the source text from where it started up to the current char,
+    /// and report it along with a hint on how to fix it
+    fn create_error(
+        &mut self,
+        code: &str,
+        value: &'a str,
+        message: String,
+        help: Option<String>,
+    ) -> Token<'a> {
+        let span: Span = Span::new(self.start, self.location());
+        self.create_error_from(value, error_diagnostic(code, message, span, help))
+    }
+
+    /// Create an error token covering the source text from where it started up to the current char,
+    /// and report a diagnostic for it that can point at just part of that text
+    fn create_error_from(&mut self, value: &'a str, diagnostic: Diagnostic) -> Token<'a> {
+        let span: Span = Span::new(self.start, self.location());
+        let message: String = diagnostic.message.clone();
+        self.diagnostics.push(diagnostic);
+
+        Token::error(value, message, span)
+    }
+
+    /// Report an error in the source code, along with a hint on how to fix it
+    fn report_error(&mut self, code: &str, message: String, span: Span, help: Option<String>) {
+        self.diagnostics
+            .push(error_diagnostic(code, message, span, help));
+    }
+
+    /// Get the source text from the char at the given byte index up to the current char
+    fn text_from(&self, start: usize) -> &'a str {
+        let input: &'a str = self.input;
+        &input[start..self.pos]
+    }
+
+    /// Get the location of the current char
+    fn location(&self) -> Position {
+        Position::new(self.line, self.col, self.pos)
+    }
+
+    /// Get the char starting at the given byte index, or a null char past the end of input
+    fn char_at(&self, index: usize) -> char {
+        match self.input.as_bytes().get(index) {
+            Some(&byte) if byte.is_ascii() => byte as char,
+            Some(_) => self.input[index..].chars().next().unwrap_or('\u{0}'),
+            None => '\u{0}',
         }
+    }
+
+    /// Get the char after the current one, without moving past it
+    fn peek_char(&self) -> char {
+        self.char_at(self.next)
+    }
 
-        Token::new(value, token_type, self.line, self.col - col_offs)
+    /// Check if we've read past the last char of the input
+    fn at_end(&self) -> bool {
+        self.pos >= self.input.len()
     }
 
     /// Read over whitespace
     fn skip_whitespace(&mut self) {
         while is_whitespace(self.cur) {
-            if self.cur == '\n' {
-                self.line += 1;
-                self.col = 0;
-            }
-
             self.read_char();
         }
     }
 
+    /// Read over whitespace and comments, keeping each run as a piece of Trivia. Trailing trivia
+    /// stops after the first line break, leaving the rest to lead the next token
+    fn read_trivia(&mut self, trailing: bool) -> Vec<Trivia<'a>> {
+        let mut trivia: Vec<Trivia<'a>> = Vec::new();
+        loop {
+            let start: Position = self.location();
+            let start_index: usize = self.pos;
+            let kind: TriviaKind =
+                if self.cur == '\n' || (self.cur == '\r' && self.peek_char() == '\n') {
+                    if self.cur == '\r' {
+                        self.read_char();
+                    }
+                    self.read_char();
+                    TriviaKind::Newline
+                } else if is_whitespace(self.cur) {
+                    while is_whitespace(self.cur)
+                        && self.cur != '\n'
+                        && !(self.cur == '\r' && self.peek_char() == '\n')
+                    {
+                        self.read_char();
+                    }
+                    TriviaKind::Whitespace
+                } else if self.cur == ';' {
+                    match self.read_comment().0 {
+                        TokenType::DocComment => TriviaKind::DocComment,
+                        TokenType::BlockComment => TriviaKind::BlockComment,
+                        _ => TriviaKind::Comment,
+                    }
+                } else {
+                    break;
+                };
+
+            let span: Span = Span::new(start, self.location());
+            trivia.push(Trivia::new(kind, self.text_from(start_index), span));
+            if trailing && kind == TriviaKind::Newline {
+                break;
+            }
+        }
+        trivia
+    }
+
     /// Read the character at the next position, then advance the position
     fn read_char(&mut self) {
-        if self.next >= self.input.len() {
-            // End of input
-            self.cur = '\u{0}';
-        } else {
-            self.cur = self.input[self.next];
+        // Already past the end of input, so there's nothing left to move over
+        if self.at_end() {
+            return;
+        }
+
+        // Move the location past the char we're leaving behind
+        if self.cur == '\n' {
+            self.line += 1;
+            self.col = 0;
         }
 
         self.pos = self.next;
-        self.next += 1;
+        self.cur = self.char_at(self.pos);
+        self.next = self.pos + self.cur.len_utf8();
         self.col += 1;
     }
 
     /// Read an identifier
-    fn read_identifier(&mut self) -> String {
+    fn read_identifier(&mut self) -> &'a str {
         let start: usize = self.pos;
         while is_alnum(self.cur) {
             self.read_char();
         }
 
-        self.input[start..self.pos].iter().collect()
+        self.text_from(start)
     }
 
-    /// Read a number literal (integer or floating point)
-    fn read_number(&mut self) -> (TokenType, String) {
-        let start: usize = self.pos;
-        let mut token_type = TokenType::Int;
+    /// Warn about an identifier that mixes scripts, or that looks like a different identifier seen earlier.
+    /// Each is only reported where the identifier first appears. Like rustc, two look-alike identifiers
+    /// are only reported when one of them isn't ASCII
+    fn check_identifier(&mut self, token: &Token<'a>) {
+        let name: Cow<'a, str> = token.name();
+        if !self.identifiers.insert(name.clone()) {
+            return;
+        }
+
+        if !name.is_ascii() && !name.is_single_script() {
+            let mut scripts: Vec<&str> = Vec::new();
+            for c in name.chars() {
+                let script: Script = c.script();
+                if script != Script::Common
+                    && script != Script::Inherited
+                    && !scripts.contains(&script.full_name())
+                {
+                    scripts.push(script.full_name());
+                }
+            }
+
+            self.diagnostics.push(
+                Diagnostic::warning(
+                    format!(
+                        "Identifier [{}] mixes the {} scripts",
+                        name,
+                        scripts.join(", ")
+                    ),
+                    token.span,
+                )
+                .with_code(codes::MIXED_SCRIPT_IDENTIFIER)
+                .with_help("Write the identifier in a single script".to_owned()),
+            );
+        }
 
-        while is_digit(self.cur) {
+        let key: String = skeleton(&name).collect();
+        match self.skeletons.get(&key) {
+            Some((other, span)) if *other != name && !(name.is_ascii() && other.is_ascii()) => {
+                let diagnostic: Diagnostic = Diagnostic::warning(
+                    format!("Identifier [{}] can be confused with [{}]", name, other),
+                    token.span,
+                )
+                .with_code(codes::CONFUSABLE_IDENTIFIER)
+                .with_label(*span, format!("[{}] is used here", other))
+                .with_help("Rename one of the identifiers so they look different".to_owned());
+                self.diagnostics.push(diagnostic);
+            }
+            Some(_) => {}
+            None => {
+                self.skeletons.insert(key, (name, token.span));
+            }
+        }
+    }
+
+    /// Read a number literal: a decimal with an optional fraction and exponent, or a hexadecimal, octal
+    /// or binary integer with a 0x, 0o or 0b prefix. Digits can be separated by underscores, and a type
+    /// suffix can follow, such as the u8 in 255u8. Malformed or out of range literals are reported, and
+    /// read as error tokens
+    fn read_number(&mut self) -> Token<'a> {
+        let start_index: usize = self.pos;
+        let radix: u32 = match (self.cur, self.peek_char()) {
+            ('0', 'x' | 'X') => 16,
+            ('0', 'o' | 'O') => 8,
+            ('0', 'b' | 'B') => 2,
+            _ => 10,
+        };
+        if radix != 10 {
             self.read_char();
+            self.read_char();
+        }
 
-            // If we encounter a dot, we should only keep reading if the following char is a digit
-            if self.cur == '.' && self.next < self.input.len() && is_digit(self.input[self.next]) {
-                token_type = TokenType::Float;
+        // Take in every char that could belong to the literal, then check them all at once
+        let mut seen_dot: bool = radix != 10;
+        loop {
+            if is_alnum(self.cur) {
+                let c: char = self.cur;
                 self.read_char();
+
+                // A decimal exponent can't be followed by a fraction, but can have a sign
+                if radix == 10 && (c == 'e' || c == 'E') {
+                    seen_dot = true;
+                    if (self.cur == '+' || self.cur == '-') && is_digit(self.peek_char()) {
+                        self.read_char();
+                    }
+                }
+            } else if self.cur == '.' && !seen_dot && is_digit(self.peek_char()) {
+                seen_dot = true;
+                self.read_char();
+            } else {
+                break;
             }
         }
 
-        (token_type, self.input[start..self.pos].iter().collect())
+        // Most literals are plain ASCII integers, which can skip the checks for everything else
+        let value: &'a str = self.text_from(start_index);
+        let plain: Option<u128> = match radix {
+            10 if value.bytes().all(|b| b.is_ascii_digit()) => value.parse::<u128>().ok(),
+            _ => None,
+        };
+        let literal: LiteralValue<'a> = match plain {
+            Some(plain) => LiteralValue::Int(plain, None),
+            None => {
+                let text: Vec<char> = value.chars().collect();
+                match self.check_number(&text, radix) {
+                    Ok(literal) => literal,
+                    Err(diagnostic) => return self.create_error_from(value, *diagnostic),
+                }
+            }
+        };
+        let token_type: TokenType = match literal {
+            LiteralValue::Float(..) => TokenType::Float,
+            _ => TokenType::Int,
+        };
+
+        self.create_token(token_type, value).with_literal(literal)
     }
 
-    /// Read a quote; scans until the closing quote is found.
+    /// Check the text of a number literal and work out its value. Returns the first problem found,
+    /// with a span covering just the offending part
+    fn check_number(&self, text: &[char], radix: u32) -> Result<LiteralValue<'a>, Box<Diagnostic>> {
+        // Number literals sit on one line, so each char moves the position along by one column
+        let start: Position = self.start;
+        let mut offsets: Vec<usize> = vec![0];
+        for c in text {
+            offsets.push(offsets[offsets.len() - 1] + c.len_utf8());
+        }
+        let span = |from: usize, to: usize| -> Span {
+            Span::new(
+                Position::new(
+                    start.line,
+                    start.col + from as i32,
+                    start.offset + offsets[from],
+                ),
+                Position::new(
+                    start.line,
+                    start.col + to as i32,
+                    start.offset + offsets[to],
+                ),
+            )
+        };
+        let literal_text: String = text.iter().collect();
+        let mut i: usize = if radix == 10 { 0 } else { 2 };
+
+        // Digits, skipping separators. Decimals can use the digits of any script, which are converted to
+        // ASCII. Decimal digits are allowed in other bases so bad ones can be reported
+        let mut digits: String = String::new();
+        let mut digit_indices: Vec<usize> = Vec::new();
+        let mut has_digits: bool = false;
+        let is_radix_char =
+            |c: char| -> bool { c == '_' || is_digit(c) || (radix == 16 && c.is_ascii_hexdigit()) };
+        while i < text.len() && is_radix_char(text[i]) {
+            if text[i] != '_' {
+                let value: Option<u32> = if radix == 10 {
+                    digit_value(text[i])
+                } else {
+                    text[i].to_digit(radix)
+                };
+                if value.is_none() {
+                    return Err(Box::new(error_diagnostic(
+                        codes::INVALID_DIGIT,
+                        format!(
+                            "Invalid digit [{}] in {} literal",
+                            text[i],
+                            radix_name(radix)
+                        ),
+                        span(i, i + 1),
+                        Some(format!(
+                            "Only the digits {} can be used in {} literals",
+                            radix_digits(radix),
+                            radix_name(radix)
+                        )),
+                    )));
+                }
+                if radix == 10 {
+                    digits.push(ascii_digit(text[i]));
+                    digit_indices.push(i);
+                } else {
+                    digits.push(text[i]);
+                }
+                has_digits = true;
+            }
+            i += 1;
+        }
+        if !has_digits {
+            return Err(Box::new(error_diagnostic(
+                codes::MALFORMED_NUMBER,
+                format!(
+                    "Missing digits after [{}]",
+                    text[..2].iter().collect::<String>()
+                ),
+                span(0, i),
+                None,
+            )));
+        }
+
+        // Fraction and exponent, for decimals only
+        let mut is_float: bool = false;
+        if radix == 10 && i < text.len() && text[i] == '.' {
+            is_float = true;
+            digits.push('.');
+            i += 1;
+            while i < text.len() && (text[i] == '_' || is_digit(text[i])) {
+                if text[i] != '_' {
+                    digits.push(ascii_digit(text[i]));
+                    digit_indices.push(i);
+                }
+                i += 1;
+            }
+        }
+        if radix == 10 && i < text.len() && (text[i] == 'e' || text[i] == 'E') {
+            let exponent_start: usize = i;
+            is_float = true;
+            digits.push('e');
+            i += 1;
+            if i < text.len() && (text[i] == '+' || text[i] == '-') {
+                digits.push(text[i]);
+                i += 1;
+            }
+
+            let mut has_exponent: bool = false;
+            while i < text.len() && (text[i] == '_' || is_digit(text[i])) {
+                if text[i] != '_' {
+                    digits.push(ascii_digit(text[i]));
+                    digit_indices.push(i);
+                    has_exponent = true;
+                }
+                i += 1;
+            }
+            if !has_exponent {
+                return Err(Box::new(error_diagnostic(
+                    codes::MALFORMED_NUMBER,
+                    "Missing digits in exponent".to_owned(),
+                    span(exponent_start, i),
+                    Some("Write the power of ten after the e, as in 1.5e-3".to_owned()),
+                )));
+            }
+        }
+
+        // Every digit must come from the same script, so the literal reads the same way throughout
+        if let Some(&first) = digit_indices.first() {
+            let zero: Option<char> = digit_zero(text[first]);
+            if let Some(&other) = digit_indices.iter().find(|&&j| digit_zero(text[j]) != zero) {
+                let first_name: &str = zero.map_or("", digit_system_name);
+                let other_name: &str = digit_zero(text[other]).map_or("", digit_system_name);
+                return Err(Box::new(
+                    Diagnostic::error(
+                        format!(
+                            "Number literal [{}] mixes {} and {} digits",
+                            literal_text, first_name, other_name
+                        ),
+                        span(other, other + 1),
+                    )
+                    .with_code(codes::MIXED_DIGIT_SYSTEMS)
+                    .with_label(
+                        span(first, first + 1),
+                        format!("{} digits start here", first_name),
+                    )
+                    .with_help("Write every digit of a number in the same script".to_owned()),
+                ));
+            }
+        }
+
+        // Type suffix
+        let suffix_text: String = text[i..].iter().collect();
+        let suffix_name: &str = suffix_text.trim_start_matches('_');
+        let suffix: Option<NumberSuffix> = if suffix_name.is_empty() {
+            None
+        } else {
+            let problem: Option<String> = match NumberSuffix::from_name(suffix_name) {
+                None => Some(format!(
+                    "Invalid suffix [{}] on number literal",
+                    suffix_name
+                )),
+                Some(suffix) if suffix.is_float() && radix != 10 => Some(format!(
+                    "Float suffix [{}] on {} literal",
+                    suffix,
+                    radix_name(radix)
+                )),
+                Some(suffix) if !suffix.is_float() && is_float => {
+                    Some(format!("Integer suffix [{}] on float literal", suffix))
+                }
+                Some(_) => None,
+            };
+            if let Some(message) = problem {
+                return Err(Box::new(error_diagnostic(
+                    codes::INVALID_NUMBER_SUFFIX,
+                    message,
+                    span(i, text.len()),
+                    Some("Valid suffixes are i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64, and only decimals can be floats".to_owned()),
+                )));
+            }
+            NumberSuffix::from_name(suffix_name)
+        };
+
+        // Value
+        if is_float || suffix.is_some_and(|s| s.is_float()) {
+            let value: f64 = digits.parse::<f64>().unwrap_or(f64::INFINITY);
+            let in_range: bool = match suffix {
+                Some(NumberSuffix::F32) => (value as f32).is_finite(),
+                _ => value.is_finite(),
+            };
+            if !in_range {
+                let ty: String = suffix.unwrap_or(NumberSuffix::F64).to_string();
+                return Err(Box::new(error_diagnostic(
+                    codes::NUMBER_OUT_OF_RANGE,
+                    format!(
+                        "Float literal [{}] is out of range for [{}]",
+                        literal_text, ty
+                    ),
+                    span(0, text.len()),
+                    None,
+                )));
+            }
+            return Ok(LiteralValue::Float(value, suffix));
+        }
+
+        let max: u128 = suffix.map_or(u128::MAX, |s| s.max_magnitude());
+        match u128::from_str_radix(&digits, radix) {
+            Ok(value) if value <= max => Ok(LiteralValue::Int(value, suffix)),
+            _ => {
+                let ty: String = suffix.unwrap_or(NumberSuffix::U128).to_string();
+                Err(Box::new(error_diagnostic(
+                    codes::NUMBER_OUT_OF_RANGE,
+                    format!(
+                        "Integer literal [{}] is out of range for [{}]",
+                        literal_text, ty
+                    ),
+                    span(0, text.len()),
+                    Some(format!("The largest [{}] literal is {}", ty, max)),
+                )))
+            }
+        }
+    }
+
+    /// Read a quote; scans until the closing quote is found, decoding escape sequences along the way.
     /// If it scans until EOF without finding a close, we have an error.
-    fn read_quote(&mut self) -> (TokenType, String, i32) {
+    fn read_quote(&mut self) -> Token<'a> {
         let quote: char = self.cur;
-        let line: i32 = self.line;
-        let col: i32 = self.col;
-        let mut token_type = TokenType::String;
+        let quote_index: usize = self.pos;
+        let token_type: TokenType = match quote {
+            '\'' => TokenType::Char,
+            '`' => TokenType::FormString,
+            _ => TokenType::String,
+        };
+
+        self.read_char();
+        let token_start: Position = self.start;
+        let start: usize = self.pos;
+        let mut text_index: usize = self.pos;
+        let mut text_start: Position = self.location();
+        let mut decoded: Option<String> = None;
+        let mut segments: Vec<FormSegment<'a>> = Vec::new();
+
+        while self.cur != quote {
+            // If we get to the end of the file without closing the quote, we have an error
+            if self.at_end() {
+                return self.create_error(
+                    codes::UNCLOSED_QUOTE,
+                    self.text_from(quote_index),
+                    format!("Unclosed quote [{}]", quote),
+                    Some(format!("Add a closing [{}]", quote)),
+                );
+            }
+
+            if self.cur == '\\' {
+                // Text is borrowed from the source code until an escape sequence has to be decoded
+                let escape_index: usize = self.pos;
+                let c: char = self.read_escape();
+                decoded
+                    .get_or_insert_with(|| self.input[text_index..escape_index].to_owned())
+                    .push(c);
+            } else if token_type == TokenType::FormString && self.cur == '{' {
+                // Form strings embed expressions in braces, which we scan as tokens of their own
+                let text: Cow<'a, str> = self.take_text(&mut decoded, text_index);
+                if !text.is_empty() {
+                    let span: Span = Span::new(text_start, self.location());
+                    segments.push(FormSegment::Text(text, span));
+                }
 
-        if quote == '\'' {
-            token_type = TokenType::Char;
-        } else if quote == '`' {
-            token_type = TokenType::FormString;
+                let code_start: Position = self.location();
+                let code: Option<Vec<Token<'a>>> = self.read_interpolation(quote);
+                self.start = token_start;
+                match code {
+                    Some(tokens) => {
+                        let span: Span = Span::new(code_start, self.location());
+                        segments.push(FormSegment::Code(tokens, span));
+                    }
+                    None => {
+                        // The error covers the form string up to its closing quote, if it has one
+                        self.read_char();
+                        return self.create_error(
+                            codes::UNCLOSED_INTERPOLATION,
+                            self.text_from(quote_index),
+                            "Unclosed interpolation in form string".to_owned(),
+                            Some(
+                                "Close the interpolation with [}], or write [\\{] for a literal brace"
+                                    .to_owned(),
+                            ),
+                        );
+                    }
+                }
+                text_index = self.pos;
+                text_start = self.location();
+            } else {
+                if let Some(decoded) = &mut decoded {
+                    decoded.push(self.cur);
+                }
+                self.read_char();
+            }
         }
 
+        let buffer: &'a str = self.text_from(start);
+        let contents: Cow<'a, str> = self.take_text(&mut decoded, text_index);
+        if token_type == TokenType::FormString && !contents.is_empty() {
+            let span: Span = Span::new(text_start, self.location());
+            segments.push(FormSegment::Text(contents.clone(), span));
+        }
         self.read_char();
-        let start: usize = self.pos;
-        let mut closed = true;
-        let mut num_lines = 0;
+        let text: &'a str = self.text_from(quote_index);
+
+        match token_type {
+            TokenType::String => {
+                return self
+                    .create_token(token_type, text)
+                    .with_literal(LiteralValue::Str(contents));
+            }
+            TokenType::FormString => {
+                return self
+                    .create_token(token_type, text)
+                    .with_literal(LiteralValue::Form(segments));
+            }
+            _ => {}
+        }
+
+        // If we've read a char literal, check that it holds exactly one char
+        let mut chars = contents.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if !is_unescaped_char(buffer) => self
+                .create_token(token_type, text)
+                .with_literal(LiteralValue::Char(c)),
+            _ => self.create_error(
+                codes::INVALID_CHAR_LITERAL,
+                text,
+                format!("Invalid char literal [{}]", buffer),
+                Some(
+                    "A char literal holds a single char, an escape sequence or a \\u code point"
+                        .to_owned(),
+                ),
+            ),
+        }
+    }
+
+    /// Take the text of a quote read since the given byte index. It's borrowed from the source code,
+    /// unless it held escape sequences that had to be decoded
+    fn take_text(&self, decoded: &mut Option<String>, start: usize) -> Cow<'a, str> {
+        match decoded.take() {
+            Some(text) => Cow::Owned(text),
+            None => Cow::Borrowed(self.text_from(start)),
+        }
+    }
+
+    /// Check if the current char opens a raw string: an r, any number of #s, then a double quote
+    fn at_raw_string(&self) -> bool {
+        if self.cur != 'r' {
+            return false;
+        }
 
-        // Look back 2 chars to check if we're escaping the next char. Account for escaped backslash
-        let mut last = '\u{0}';
-        let mut last2 = '\u{0}';
+        let rest: &[u8] = &self.input.as_bytes()[self.next..];
+        let hashes: usize = rest.iter().take_while(|b| **b == b'#').count();
+        rest.get(hashes) == Some(&b'"')
+    }
 
-        while self.cur != quote || (last == '\\' && last2 != '\\') {
-            last2 = last;
-            last = self.cur;
+    /// Read a raw string. Raw strings have no escape sequences, and end at the first quote followed by
+    /// as many #s as they opened with, so quotes can be embedded by opening with more #s
+    fn read_raw_string(&mut self) -> Token<'a> {
+        let start: usize = self.pos;
+        self.read_char();
+        let mut hashes: usize = 0;
+        while self.cur == '#' {
+            hashes += 1;
             self.read_char();
+        }
+        self.read_char();
+        let contents_start: usize = self.pos;
+
+        loop {
+            if self.at_end() {
+                let opening: String = format!("r{}\"", "#".repeat(hashes));
+                return self.create_error(
+                    codes::UNCLOSED_QUOTE,
+                    self.text_from(start),
+                    format!("Unclosed raw string [{}]", opening),
+                    Some(format!("Add a closing [\"{}]", "#".repeat(hashes))),
+                );
+            }
 
-            // Allow multiline strings
-            if self.cur == '\n' {
-                num_lines += 1;
+            let closes: bool = self.cur == '"'
+                && self.input.as_bytes()[self.next..]
+                    .iter()
+                    .take(hashes)
+                    .filter(|b| **b == b'#')
+                    .count()
+                    == hashes;
+            if closes {
+                let contents: &'a str = self.text_from(contents_start);
+                for _ in 0..=hashes {
+                    self.read_char();
+                }
+                return self
+                    .create_token(TokenType::String, self.text_from(start))
+                    .with_literal(LiteralValue::Str(Cow::Borrowed(contents)));
             }
 
-            // If we get to the end of the file without closing the string, log an error
-            if self.cur == '\u{0}' {
-                log_error(format!(
-                    "Unclosed quote [{}] on line {} col {}",
-                    quote, line, col
-                ));
-                self.error_count += 1;
-                closed = false;
-                break;
+            self.read_char();
+        }
+    }
+
+    /// Read an expression embedded in a form string, from its opening brace up to and including its
+    /// closing brace. Returns None if the form string's closing quote or the end of the file is reached first
+    fn read_interpolation(&mut self, quote: char) -> Option<Vec<Token<'a>>> {
+        self.start = self.location();
+        let open_index: usize = self.pos;
+        self.read_char();
+        let open: Token<'a> = self.create_token(TokenType::LeftBrace, self.text_from(open_index));
+
+        // The form string's own text covers the interpolation, so its tokens don't need trivia; reading
+        // any would also swallow the string's text after the closing brace
+        let lossless: bool = std::mem::replace(&mut self.lossless, false);
+        let tokens: Option<Vec<Token<'a>>> = self.read_interpolation_tokens(vec![open], quote);
+        self.lossless = lossless;
+        tokens
+    }
+
+    /// Scan the tokens of an interpolation after its opening brace, up to and including its closing brace.
+    /// Form strings can be nested inside it, but a quote that doesn't close a nested string of its own is
+    /// the enclosing string's closing quote: scanning stops in front of it, and returns None
+    fn read_interpolation_tokens(
+        &mut self,
+        mut tokens: Vec<Token<'a>>,
+        quote: char,
+    ) -> Option<Vec<Token<'a>>> {
+        // Scan until the brace that closes the interpolation, skipping over any nested braces
+        let mut depth: usize = 0;
+        loop {
+            self.skip_whitespace();
+            let (pos, next, cur, line, col) = (self.pos, self.next, self.cur, self.line, self.col);
+            let reported: usize = self.diagnostics.len();
+
+            let token: Token<'a> = self.next_token();
+            match token.token_type {
+                TokenType::EOF => return None,
+                TokenType::Error if cur == quote => {
+                    self.reach = self.reach.max(self.pos);
+                    (self.pos, self.next, self.cur, self.line, self.col) =
+                        (pos, next, cur, line, col);
+                    self.diagnostics.truncate(reported);
+                    return None;
+                }
+                TokenType::LeftBrace => depth += 1,
+                TokenType::RightBrace if depth == 0 => {
+                    tokens.push(token);
+                    return Some(tokens);
+                }
+                TokenType::RightBrace => depth -= 1,
+                _ => {}
             }
+            tokens.push(token);
         }
+    }
 
-        let end: usize = self.pos;
-        let buffer: String = self.input[start..end].iter().collect();
+    /// Read an escape sequence, starting at its backslash, and decode it into the char it stands for.
+    /// Invalid escapes are reported with a span covering just the escape, and decode to U+FFFD
+    fn read_escape(&mut self) -> char {
+        let start: Position = self.location();
+        let start_index: usize = self.pos;
+        self.read_char();
 
-        // If we've read a char literal, check that it is valid
-        if closed && quote == '\'' && !is_valid_char(&buffer) {
-            log_error(format!(
-                "Invalid char literal [{}] on line {} col {}",
-                buffer, line, col
-            ));
-            self.error_count += 1;
+        // Leave the end of the file for the caller to report as an unclosed quote
+        if self.at_end() {
+            return char::REPLACEMENT_CHARACTER;
         }
 
+        let escaped: char = self.cur;
         self.read_char();
-        (token_type, buffer, num_lines)
+        match escaped {
+            '\\' => '\\',   // Backslash
+            'a' => '\u{7}', // Alert
+            'b' => '\u{8}', // Backspace
+            'f' => '\u{c}', // Page break (form feed)
+            'n' => '\n',    // Newline (line feed)
+            'r' => '\r',    // Carriage return
+            't' => '\t',    // Horizontal tab
+            'v' => '\u{b}', // Vertical tab
+            '0' => '\0',    // Null char
+            '\'' | '"' | '`' => escaped,
+            '{' | '}' => escaped, // Braces, for form strings
+            'u' => self.read_unicode_escape(start, start_index),
+
+            // Everything else is invalid
+            _ => {
+                self.report_error(
+                    codes::INVALID_ESCAPE,
+                    format!("Invalid escape sequence [\\{}]", escaped),
+                    Span::new(start, self.location()),
+                    Some(
+                        "Valid escapes are \\\\ \\a \\b \\f \\n \\r \\t \\v \\0 \\' \\\" \\` and \\u code points"
+                            .to_owned(),
+                    ),
+                );
+                char::REPLACEMENT_CHARACTER
+            }
+        }
     }
 
-    /// Read a comment: comments are from the opening char until the end of the line (or EOF)
-    fn read_comment(&mut self) -> String {
-        let start: usize = self.pos;
-        while self.cur != '\n' && self.cur != '\u{0}' {
+    /// Read the code point of a unicode escape, after its \u. It is either exactly 4 hex digits,
+    /// or 1 to 6 hex digits in braces. Surrogates and values past U+10FFFF are reported
+    fn read_unicode_escape(&mut self, start: Position, start_index: usize) -> char {
+        let braced: bool = self.cur == '{';
+        let max_digits: usize = if braced { usize::MAX } else { 4 };
+        if braced {
+            self.read_char();
+        }
+
+        let mut digits: String = String::new();
+        while self.cur.is_ascii_hexdigit() && digits.len() < max_digits {
+            digits.push(self.cur);
             self.read_char();
         }
 
-        self.input[start..self.pos].iter().collect()
+        // Check the shape of the escape
+        let well_formed: bool = if braced {
+            let closed: bool = self.cur == '}';
+            if closed {
+                self.read_char();
+            }
+            closed && !digits.is_empty()
+        } else {
+            digits.len() == 4
+        };
+        let span: Span = Span::new(start, self.location());
+        if !well_formed {
+            self.report_error(
+                codes::MALFORMED_UNICODE_ESCAPE,
+                format!("Malformed unicode escape [{}]", self.text_from(start_index)),
+                span,
+                Some("Write exactly 4 hex digits as in \\u00e9, or 1 to 6 in braces as in \\u{1f600}".to_owned()),
+            );
+            return char::REPLACEMENT_CHARACTER;
+        }
+
+        // Check that the code point is a char
+        let code_point: Option<u32> = if digits.len() <= 6 {
+            u32::from_str_radix(&digits, 16).ok()
+        } else {
+            None
+        };
+        match code_point.and_then(char::from_u32) {
+            Some(c) => c,
+            None => {
+                let reason: &str = match code_point {
+                    Some(0xD800..=0xDFFF) => "is a surrogate",
+                    _ => "is past U+10FFFF",
+                };
+                self.report_error(
+                    codes::INVALID_CODE_POINT,
+                    format!("Unicode escape [{}] {}", self.text_from(start_index), reason),
+                    span,
+                    Some("Unicode escapes must be scalar values: U+0000 to U+D7FF or U+E000 to U+10FFFF".to_owned()),
+                );
+                char::REPLACEMENT_CHARACTER
+            }
+        }
     }
 
-    /// Read an operator: an operator can be 1, 2, or 3 chars long
-    fn read_operator(&mut self, base: TokenType) -> (TokenType, String) {
-        let mut token_type = base;
-        let mut buffer: String = self.cur.to_string();
-        let mut to_scan: usize = 0;
+    /// Read a comment: comments are from the opening char until the end of the line (or EOF), leaving out
+    /// the \n or \r\n that ends it. Comments opened with ;; are doc comments, and comments opened with ;*
+    /// are block comments
+    fn read_comment(&mut self) -> (TokenType, &'a str) {
+        let start: usize = self.pos;
+        if self.peek_char() == '*' {
+            self.read_block_comment();
+            return (TokenType::BlockComment, self.text_from(start));
+        }
+
+        let token_type: TokenType = match self.peek_char() {
+            ';' => TokenType::DocComment,
+            _ => TokenType::Comment,
+        };
+        while self.cur != '\n' && !(self.cur == '\r' && self.peek_char() == '\n') && !self.at_end()
+        {
+            self.read_char();
+        }
 
-        // Check up to the 3 chars from the current position if we can
-        for offs in (1..=3).rev() {
-            if self.pos + offs <= self.input.len() {
-                let op: String = self.input[self.pos..self.pos + offs].iter().collect();
-                match parse_op(op.as_str()) {
-                    Some(op_type) => {
-                        token_type = op_type;
-                        buffer = op;
-                        to_scan += offs;
-                        break;
-                    }
-                    None => {}
+        (token_type, self.text_from(start))
+    }
+
+    /// Read a block comment from its opening ;* up to the *; that closes it, including any nested
+    /// block comments along the way
+    fn read_block_comment(&mut self) {
+        let mut openers: Vec<Span> = Vec::new();
+        loop {
+            let start: Position = self.location();
+            if self.cur == ';' && self.peek_char() == '*' {
+                self.read_char();
+                self.read_char();
+                openers.push(Span::new(start, self.location()));
+            } else if self.cur == '*' && self.peek_char() == ';' {
+                self.read_char();
+                self.read_char();
+                openers.pop();
+                if openers.is_empty() {
+                    return;
                 }
+            } else if self.at_end() {
+                break;
+            } else {
+                self.read_char();
             }
         }
 
-        //  Catch up the internal pointer
-        for _ in 0..to_scan {
+        // Point at the outermost comment, and at the innermost one left open if it's nested
+        let mut diagnostic: Diagnostic =
+            Diagnostic::error("Unclosed block comment".to_owned(), openers[0])
+                .with_code(codes::UNCLOSED_BLOCK_COMMENT)
+                .with_help("Add a closing [*;] for each [;*]".to_owned());
+        if openers.len() > 1 {
+            diagnostic = diagnostic.with_label(
+                openers[openers.len() - 1],
+                "nested block comment opened here isn't closed either".to_owned(),
+            );
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Read an operator that the trie matched, which is len bytes long. The trie always gives the longest
+    /// match, so operators don't need to be looked up again while reading them
+    fn read_operator(&mut self, len: usize) -> &'a str {
+        let start: usize = self.pos;
+        while self.pos < start + len {
             self.read_char();
         }
+        self.text_from(start)
+    }
+}
+
+/// Lexers hand out tokens one at a time, scanning each only when it's asked for
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
 
-        (token_type, buffer)
+    fn next(&mut self) -> Option<Token<'a>> {
+        self.peek_nth(0)?;
+        self.lookahead.pop_front()
     }
 }
 
 /// Convert raw Rumil source code text into a vector of tokens.
-/// If there was an error, return an error message instead
-pub fn scan(source_code: String, file_path: &String) -> Result<Vec<Token>, String> {
-    let mut lexer: Lexer = Lexer::new(source_code);
-    let mut tokens: Vec<Token> = Vec::new();
-    let mut token: Token = lexer.next_token();
+/// Problems in the source code become Error tokens and are added to the diagnostics, but scanning carries on past them.
+/// If there is no source code at all, return an error message instead
+pub fn scan<'a>(
+    source_code: &'a str,
+    file_path: &String,
+    diagnostics: &mut Diagnostics,
+) -> Result<Vec<Token<'a>>, String> {
+    scan_tokens(Lexer::new(source_code), file_path, diagnostics)
+}
 
-    // Scan tokens
-    while token.token_type != TokenType::EOF {
-        tokens.push(token);
-        token = lexer.next_token();
-    }
+/// Convert source code written in a dialect into a vector of standard Rumil tokens, the same way as scan
+pub fn scan_dialect<'a>(
+    source_code: &'a str,
+    file_path: &String,
+    dialect: &'a Dialect,
+    diagnostics: &mut Diagnostics,
+) -> Result<Vec<Token<'a>>, String> {
+    scan_tokens(
+        Lexer::new(source_code).with_dialect(dialect),
+        file_path,
+        diagnostics,
+    )
+}
+
+/// Collect every token from a Lexer, reporting its problems and logging the tokens if debugging
+fn scan_tokens<'a>(
+    mut lexer: Lexer<'a>,
+    file_path: &String,
+    diagnostics: &mut Diagnostics,
+) -> Result<Vec<Token<'a>>, String> {
+    let tokens: Vec<Token<'a>> = lexer.by_ref().collect();
 
     // Report errors
-    if tokens.len() == 0 {
+    diagnostics.append(&mut lexer.take_diagnostics());
+    if tokens.is_empty() {
         return Err(format!("No source code found in {}", file_path));
     }
-    if lexer.error_count > 0 {
-        let mut s: &str = "";
-        if lexer.error_count > 1 {
-            s = "s";
-        }
-
-        return Err(format!(
-            "{} syntax error{} encountered in {}",
-            lexer.error_count, s, file_path
-        ));
-    }
 
     // Log the tokens if debugging
     if debugging() {
@@ -302,6 +1166,22 @@ pub fn scan(source_code: String, file_path: &String) -> Result<Vec<Token>, Strin
     Ok(tokens)
 }
 
+/// Scan source code losslessly: whitespace and comments are attached to the tokens around them as
+/// trivia, and the final EOF token holds any trivia after the last token. Joining the tokens with
+/// source_text rebuilds the source code byte for byte
+pub fn scan_lossless<'a>(source_code: &'a str, diagnostics: &mut Diagnostics) -> Vec<Token<'a>> {
+    let mut lexer: Lexer = Lexer::lossless(source_code);
+    let tokens: Vec<Token<'a>> = lexer.by_ref().collect();
+
+    diagnostics.append(&mut lexer.take_diagnostics());
+    tokens
+}
+
+/// Rebuild the source code of a losslessly scanned token stream
+pub fn source_text(tokens: &[Token]) -> String {
+    tokens.iter().map(Token::full_text).collect()
+}
+
 // Scanning utils
 // --------------
 
@@ -310,84 +1190,65 @@ fn is_whitespace(c: char) -> bool {
     c == ' ' || c == '\n' || c == '\r' || c == '\t'
 }
 
-/// Utility function to tell us if a char is a letter
-fn is_letter(c: char) -> bool {
-    ('a' <= c && c <= 'z') || ('A' <= c && c <= 'Z')
+/// Utility function to build an error diagnostic with a code, along with a hint on how to fix it
+fn error_diagnostic(code: &str, message: String, span: Span, help: Option<String>) -> Diagnostic {
+    let mut diagnostic: Diagnostic = Diagnostic::error(message, span).with_code(code);
+    diagnostic.help = help;
+    diagnostic
 }
 
-/// Utility function to tell us if a char is a digit
-fn is_digit(c: char) -> bool {
-    '0' <= c && c <= '9'
+/// Utility function to tell us if a char can start an identifier, following UAX #31 (XID_Start)
+fn is_letter(c: char) -> bool {
+    c.is_xid_start()
 }
 
-/// Utility function to tell us if a char is alphanumeric (including underscores)
-fn is_alnum(c: char) -> bool {
-    c == '_' || is_letter(c) || is_digit(c)
+/// Utility function to tell us if a char is a decimal digit, in any script
+fn is_digit(c: char) -> bool {
+    digit_zero(c).is_some()
 }
 
-/// Utility function to tell us if a char is a quotation mark
-fn is_quote(c: char) -> bool {
-    c == '"' || c == '\'' || c == '`'
+/// Utility function to convert a decimal digit from any script into the matching ASCII digit
+fn ascii_digit(c: char) -> char {
+    char::from_digit(digit_value(c).unwrap_or(0), 10).unwrap_or('0')
 }
 
-/// Utility function to tell us if a string is a valid char literal
-fn is_valid_char(c: &str) -> bool {
-    if c.len() == 1 && !is_unescaped_char(c) {
-        return true;
+/// Utility function to get the name of a number base, for messages
+fn radix_name(radix: u32) -> &'static str {
+    match radix {
+        2 => "binary",
+        8 => "octal",
+        16 => "hexadecimal",
+        _ => "decimal",
     }
-
-    is_valid_unicode_point(c) || is_valid_escaped_char(c)
 }
 
-/// Utility function to tell us if a literal is a valid unicode point
-fn is_valid_unicode_point(u: &str) -> bool {
-    if !u.starts_with("\\u") || u.len() == 2 || u.len() > 10 {
-        return false;
-    }
-
-    // Each digit past the first 2 must be a valid hexadecimal digit
-    let valid = |x: char| -> bool {
-        (x >= '0' && x <= '9') || (x >= 'A' && x <= 'F') || (x >= 'a' && x <= 'f')
-    };
-
-    for i in 2..u.len() {
-        if let Some(x) = u.chars().nth(i)
-            && !valid(x)
-        {
-            return false;
-        }
+/// Utility function to list the digits of a number base, for messages
+fn radix_digits(radix: u32) -> &'static str {
+    match radix {
+        2 => "0 and 1",
+        8 => "0 to 7",
+        16 => "0 to 9 and a to f",
+        _ => "0 to 9",
     }
+}
 
-    return true;
+/// Utility function to tell us if a char can continue an identifier, following UAX #31 (XID_Continue).
+/// This covers letters, digits, underscores and combining marks
+fn is_alnum(c: char) -> bool {
+    c.is_xid_continue()
 }
 
-/// Utility function to tell us if a literal is a valid escape sequence
-fn is_valid_escaped_char(e: &str) -> bool {
-    match e {
-        r"\\" => true, // Backslash
-        r"\a" => true, // Alert
-        r"\b" => true, // Backspace
-        r"\f" => true, // Page break (form feed)
-        r"\n" => true, // Newline (line feed)
-        r"\r" => true, // Carriage return
-        r"\t" => true, // Horizontal tab
-        r"\v" => true, // Vertical tab
-        r"\'" => true, // Single quote
-        r"\0" => true, // Null char
-
-        // Everything else is invalid
-        _ => false,
-    }
+/// Utility function to tell us if a char is a quotation mark
+fn is_quote(c: char) -> bool {
+    c == '"' || c == '\'' || c == '`'
 }
 
-/// Utility function to tell us if a literal is an unescaped char
+/// Utility function to tell us if a char literal holds a char that must be escaped
 fn is_unescaped_char(c: &str) -> bool {
     match c {
-        "\\" => true, // Backslash
         "\n" => true, // Newline (line feed)
         "\r" => true, // Carriage return
         "\t" => true, // Horizontal tab
-        "\'" => true, // Single quote
         "\0" => true, // Null char
 
         _ => false,
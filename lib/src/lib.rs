@@ -1,21 +1,54 @@
-mod ast;
-mod lexer;
+pub mod ast;
+pub mod codes;
+pub mod diagnostic;
+pub mod dialect;
+pub mod lexer;
 mod log;
-mod token;
+mod numerals;
+pub mod operators;
+mod parser;
+pub mod printer;
+pub mod span;
+pub mod syntax;
+pub mod token;
+pub mod transpile;
 
-use std::{ffi::CStr, fs::read_to_string, os::raw::c_char, ptr::null_mut};
+use std::{
+    ffi::CStr,
+    fs::{read_to_string, write},
+    os::raw::c_char,
+    path::Path,
+    ptr::null_mut,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
 
 use crate::{
     ast::Ast,
+    codes::{ErrorCode, explain},
+    diagnostic::{Diagnostics, Severity},
+    dialect::Dialect,
     lexer::scan,
-    log::{log_error, log_message, set_debugging},
+    log::{
+        DiagnosticFormat, diagnostic_format, log_diagnostics, log_error, log_file_error,
+        log_message, set_debugging,
+    },
     token::Token,
+    transpile::{Transpiled, transpile},
 };
 
+// The dialect later calls to parse_file read source code in, if it isn't plain Rumil
+static DIALECT: Mutex<Option<Dialect>> = Mutex::new(None);
+
+// Environment variable naming a dialect file, used when no dialect has been set through the FFI
+const DIALECT_ENV: &str = "RUMIL_DIALECT";
+
 /// Parses the source file passed in and returns a pointer to the resulting AST in memory.
 /// If any errors arise, they are printed to stderr and a null pointer is returned.
+///
+/// # Safety
+/// filepath must be a valid, null-terminated C string
 #[unsafe(no_mangle)]
-pub extern "C" fn parse_file(filepath: *const c_char, verbose: bool) -> *mut Ast {
+pub unsafe extern "C" fn parse_file(filepath: *const c_char, verbose: bool) -> *mut Ast {
     set_debugging(verbose);
     let r_filepath: String;
 
@@ -29,22 +62,229 @@ pub extern "C" fn parse_file(filepath: *const c_char, verbose: bool) -> *mut Ast
     let source_code: String = match read_to_string(&r_filepath) {
         Ok(contents) => contents,
         Err(msg) => {
-            log_error(format!("Error reading {}: {}", r_filepath, msg));
+            log_file_error(
+                format!("Error reading {}: {}", r_filepath, msg),
+                &r_filepath,
+            );
             return null_mut();
         }
     };
 
+    let Some(dialect) = selected_dialect() else {
+        return null_mut();
+    };
+
+    // Source code in a dialect is transpiled to canonical Rumil before it's scanned
+    let transpiled: Option<Transpiled> = dialect.as_ref().map(|dialect| {
+        log_message(format!("Transpiling from the {} dialect...", dialect.name));
+        transpile(&source_code, dialect)
+    });
+
+    match parse_source(&source_code, &r_filepath, transpiled.as_ref()) {
+        Some(ast) => Box::into_raw(ast),
+        None => null_mut(),
+    }
+}
+
+/// Transpiles a source file written in the selected dialect into canonical Rumil, writing it to outpath
+/// along with a source map at outpath.map. The transpiled code is then parsed, and any problems in it are
+/// reported against the dialect source file. Returns false if the file couldn't be transpiled or has errors
+///
+/// # Safety
+/// filepath and outpath must be valid, null-terminated C strings
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn transpile_file(filepath: *const c_char, outpath: *const c_char) -> bool {
+    let r_filepath: String;
+    let r_outpath: String;
+    unsafe {
+        r_filepath = CStr::from_ptr(filepath).to_string_lossy().into_owned();
+        r_outpath = CStr::from_ptr(outpath).to_string_lossy().into_owned();
+    }
+
+    let source_code: String = match read_to_string(&r_filepath) {
+        Ok(contents) => contents,
+        Err(msg) => {
+            log_file_error(
+                format!("Error reading {}: {}", r_filepath, msg),
+                &r_filepath,
+            );
+            return false;
+        }
+    };
+
+    let Some(dialect) = selected_dialect() else {
+        return false;
+    };
+    let Some(dialect) = dialect.as_ref() else {
+        log_file_error(
+            format!(
+                "No dialect to transpile from, set {} to a dialect file",
+                DIALECT_ENV
+            ),
+            &r_filepath,
+        );
+        return false;
+    };
+
+    // Write out the transpiled code and its source map
+    log_message(format!(
+        "Transpiling {} from the {} dialect...",
+        r_filepath, dialect.name
+    ));
+    let transpiled: Transpiled = transpile(&source_code, dialect);
+    let map_path: String = format!("{}.map", r_outpath);
+    let map: String = transpiled.source_map.to_json();
+    for (path, contents) in [(&r_outpath, &transpiled.code), (&map_path, &map)] {
+        if let Err(msg) = write(path, contents) {
+            log_file_error(format!("Error writing {}: {}", path, msg), path);
+            return false;
+        }
+    }
+    log_message(format!("Wrote {} and {}", r_outpath, map_path));
+
+    parse_source(&source_code, &r_filepath, Some(&transpiled)).is_some()
+}
+
+/// Get the dialect source code is read in, loading the one named by the environment if none has been set.
+/// Returns None if that dialect couldn't be loaded, after printing why
+fn selected_dialect() -> Option<MutexGuard<'static, Option<Dialect>>> {
+    let mut dialect: MutexGuard<Option<Dialect>> =
+        DIALECT.lock().unwrap_or_else(PoisonError::into_inner);
+    if dialect.is_none()
+        && let Ok(dialect_path) = std::env::var(DIALECT_ENV)
+    {
+        match Dialect::load(Path::new(&dialect_path)) {
+            Ok(loaded) => *dialect = Some(loaded),
+            Err(msg) => {
+                log_file_error(msg, &dialect_path);
+                return None;
+            }
+        }
+    }
+    Some(dialect)
+}
+
+/// Scan and parse source code, reporting every problem against the file the user wrote. Source code
+/// written in a dialect comes already transpiled, and problems in the transpiled code are mapped back
+/// to the dialect source code. Returns the AST if there were no errors
+fn parse_source(
+    source_code: &str,
+    file_path: &String,
+    transpiled: Option<&Transpiled>,
+) -> Option<Box<Ast>> {
     // Tokenize source code
     log_message("Scanning source code...".to_owned());
-    let tokens: Vec<Token> = match scan(source_code, &r_filepath) {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let code: &str = transpiled.map_or(source_code, |transpiled| &transpiled.code);
+    let tokens: Vec<Token> = match scan(code, file_path, &mut diagnostics) {
         Ok(tk) => tk,
         Err(msg) => {
-            log_error(msg);
-            return null_mut();
+            log_file_error(msg, file_path);
+            return None;
         }
     };
 
-    // Parse tokens into syntax tree
+    // Parse tokens into syntax tree, even if scanning failed, so every problem is reported at once
     log_message("Parsing tokens...".to_owned());
-    Box::into_raw(Ast::new(tokens))
+    let ast: Box<Ast> = Ast::new(tokens, &mut diagnostics);
+
+    // Report problems
+    if let Some(transpiled) = transpiled {
+        transpiled.source_map.remap(&mut diagnostics);
+    }
+    diagnostics.sort();
+    log_diagnostics(&diagnostics, source_code, file_path);
+
+    let error_count: usize = diagnostics.count(Severity::Error);
+    match error_count {
+        0 => Some(ast),
+        _ => {
+            // Keep the JSON output to one diagnostic per line
+            if diagnostic_format() == DiagnosticFormat::Human {
+                let s: &str = if error_count > 1 { "s" } else { "" };
+                log_error(format!(
+                    "{} syntax error{} encountered in {}",
+                    error_count, s, file_path
+                ));
+            }
+            None
+        }
+    }
+}
+
+/// Selects how diagnostics are rendered by later calls to parse_file: "human" or "json".
+/// This overrides the RUMIL_DIAGNOSTIC_FORMAT environment variable. Returns false if the format is unknown.
+///
+/// # Safety
+/// format must be a valid, null-terminated C string
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn set_diagnostic_format(format: *const c_char) -> bool {
+    let r_format: String;
+    unsafe {
+        r_format = CStr::from_ptr(format).to_string_lossy().into_owned();
+    }
+
+    match DiagnosticFormat::from_name(&r_format) {
+        Some(format) => {
+            log::set_diagnostic_format(format);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Selects the dialect later calls to parse_file read source code in, loading it from a .toml or .json
+/// file. This overrides the RUMIL_DIALECT environment variable, and an empty path clears it again.
+/// Returns false if the dialect couldn't be loaded, after printing why
+///
+/// # Safety
+/// path must be a valid, null-terminated C string
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn set_dialect(path: *const c_char) -> bool {
+    let r_path: String;
+    unsafe {
+        r_path = CStr::from_ptr(path).to_string_lossy().into_owned();
+    }
+
+    let mut dialect: MutexGuard<Option<Dialect>> =
+        DIALECT.lock().unwrap_or_else(PoisonError::into_inner);
+    if r_path.is_empty() {
+        *dialect = None;
+        return true;
+    }
+
+    match Dialect::load(Path::new(&r_path)) {
+        Ok(loaded) => {
+            *dialect = Some(loaded);
+            true
+        }
+        Err(msg) => {
+            log_file_error(msg, &r_path);
+            false
+        }
+    }
+}
+
+/// Prints the long-form explanation of an error code, such as R0001, to stdout.
+/// Returns false if the code isn't registered.
+///
+/// # Safety
+/// code must be a valid, null-terminated C string
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn explain_code(code: *const c_char) -> bool {
+    let r_code: String;
+    unsafe {
+        r_code = CStr::from_ptr(code).to_string_lossy().into_owned();
+    }
+
+    let entry: &ErrorCode = match explain(&r_code) {
+        Some(entry) => entry,
+        None => {
+            log_error(format!("Unknown error code [{}]", r_code));
+            return false;
+        }
+    };
+
+    println!("{}: {}\n\n{}", entry.code, entry.title, entry.explanation);
+    true
 }
@@ -1,8 +1,29 @@
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::{
+    io::IsTerminal,
+    sync::atomic::{AtomicBool, AtomicU8, Ordering},
+};
 
 use colored::{ColoredString, Colorize};
+use serde::Serialize;
+
+use crate::{
+    diagnostic::{Diagnostic, Diagnostics, Severity},
+    span::{Position, Span},
+};
 
 static VERBOSE: AtomicBool = AtomicBool::new(false);
+static FORMAT: AtomicU8 = AtomicU8::new(FORMAT_UNSET);
+
+// Values stored in FORMAT
+const FORMAT_UNSET: u8 = 0;
+const FORMAT_HUMAN: u8 = 1;
+const FORMAT_JSON: u8 = 2;
+
+// Environment variable that selects the diagnostic format when it hasn't been set through the FFI
+const FORMAT_ENV: &str = "RUMIL_DIAGNOSTIC_FORMAT";
+
+// Tabs are printed as this many spaces in source snippets
+const TAB: &str = "    ";
 
 /// Set the verbose flag for logging
 pub fn set_debugging(verbose: bool) {
@@ -14,6 +35,46 @@ pub fn debugging() -> bool {
     VERBOSE.load(Ordering::Relaxed)
 }
 
+/// How diagnostics are rendered
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DiagnosticFormat {
+    Human, // Annotated source snippets for people to read
+    Json,  // One JSON object per line for tools to consume
+}
+
+impl DiagnosticFormat {
+    /// Get the DiagnosticFormat with the given name, if there is one
+    pub fn from_name(name: &str) -> Option<DiagnosticFormat> {
+        match name.trim().to_lowercase().as_str() {
+            "human" => Some(DiagnosticFormat::Human),
+            "json" => Some(DiagnosticFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Set the format diagnostics are rendered in, overriding the environment variable
+pub fn set_diagnostic_format(format: DiagnosticFormat) {
+    let value: u8 = match format {
+        DiagnosticFormat::Human => FORMAT_HUMAN,
+        DiagnosticFormat::Json => FORMAT_JSON,
+    };
+    FORMAT.store(value, Ordering::Relaxed);
+}
+
+/// Get the format diagnostics are rendered in. If it hasn't been set, check the environment variable,
+/// and fall back to the human format
+pub fn diagnostic_format() -> DiagnosticFormat {
+    match FORMAT.load(Ordering::Relaxed) {
+        FORMAT_HUMAN => DiagnosticFormat::Human,
+        FORMAT_JSON => DiagnosticFormat::Json,
+        _ => std::env::var(FORMAT_ENV)
+            .ok()
+            .and_then(|name| DiagnosticFormat::from_name(&name))
+            .unwrap_or(DiagnosticFormat::Human),
+    }
+}
+
 /// Utility logging function
 fn log(prefix: ColoredString, msg: String, is_error: bool) {
     let output: String = format!("{}\n    {}\n", prefix, msg);
@@ -43,3 +104,279 @@ pub fn log_debug(msg: String) {
 pub fn log_error(msg: String) {
     log("[Parser Error]".red().bold(), msg, true);
 }
+
+/// Log a problem with a whole file, such as one that can't be read, to stderr in the selected diagnostic
+/// format. In the JSON format it's an error diagnostic with no code, spanning the start of the file
+pub fn log_file_error(msg: String, file_path: &str) {
+    match diagnostic_format() {
+        DiagnosticFormat::Human => log_error(msg),
+        DiagnosticFormat::Json => {
+            let start: Position = Position::new(1, 1, 0);
+            let diagnostic: Diagnostic = Diagnostic::error(msg, Span::new(start, start));
+            eprintln!("{}", render_json(&diagnostic, file_path));
+        }
+    }
+}
+
+// Diagnostic rendering
+// --------------------
+
+/// A span to underline in a source snippet, with the message to show next to it
+struct Annotation<'a> {
+    span: Span,               // Source range to underline
+    message: Option<&'a str>, // Text to print after the underline
+    primary: bool,            // Whether this is the diagnostic's own span, or a secondary label
+}
+
+/// A diagnostic along with the file it was found in, as written out in the JSON format
+#[derive(Serialize)]
+struct JsonDiagnostic<'a> {
+    file: &'a str,
+    #[serde(flatten)]
+    diagnostic: &'a Diagnostic,
+}
+
+/// Render every collected diagnostic to stderr, in the selected diagnostic format
+pub fn log_diagnostics(diagnostics: &Diagnostics, source_code: &str, file_path: &str) {
+    match diagnostic_format() {
+        DiagnosticFormat::Human => {
+            let color: bool = use_color();
+            for diagnostic in diagnostics.iter() {
+                eprintln!(
+                    "{}",
+                    render_diagnostic(diagnostic, source_code, file_path, color)
+                );
+            }
+        }
+        DiagnosticFormat::Json => {
+            for diagnostic in diagnostics.iter() {
+                eprintln!("{}", render_json(diagnostic, file_path));
+            }
+        }
+    }
+}
+
+/// Render a diagnostic as a single line of JSON
+pub fn render_json(diagnostic: &Diagnostic, file_path: &str) -> String {
+    let json: JsonDiagnostic = JsonDiagnostic {
+        file: file_path,
+        diagnostic,
+    };
+
+    // Serializing plain strings and numbers can't fail
+    serde_json::to_string(&json).unwrap_or_default()
+}
+
+/// Check whether diagnostics should be colored: only when stderr is a terminal and NO_COLOR is unset or empty
+fn use_color() -> bool {
+    let no_color: bool = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    !no_color && std::io::stderr().is_terminal()
+}
+
+/// Render a diagnostic in the style of rustc: a header, the location, the source lines it covers
+/// with the spans underlined, and then any notes and help
+pub fn render_diagnostic(
+    diagnostic: &Diagnostic,
+    source_code: &str,
+    file_path: &str,
+    color: bool,
+) -> String {
+    let lines: Vec<&str> = source_code.lines().collect();
+    let severity_style = |text: &str| -> ColoredString {
+        match diagnostic.severity {
+            Severity::Error => text.red().bold(),
+            Severity::Warning => text.yellow().bold(),
+            Severity::Note => text.cyan().bold(),
+        }
+    };
+    let gutter_style = |text: &str| -> ColoredString { text.blue().bold() };
+
+    // Gather the spans to underline, primary span first
+    let mut annotations: Vec<Annotation> = vec![Annotation {
+        span: trim_span(diagnostic.span, &lines),
+        message: None,
+        primary: true,
+    }];
+    annotations.extend(diagnostic.labels.iter().map(|label| Annotation {
+        span: trim_span(label.span, &lines),
+        message: Some(label.message.as_str()),
+        primary: false,
+    }));
+
+    // Pick the lines to show. Long spans only show their first and last lines
+    let mut shown: Vec<i32> = Vec::new();
+    for annotation in &annotations {
+        let (first, last): (i32, i32) = (annotation.span.start.line, annotation.span.end.line);
+        if last - first < 4 {
+            shown.extend(first..=last);
+        } else {
+            shown.extend([first, first + 1, last]);
+        }
+    }
+    shown.sort();
+    shown.dedup();
+    let width: usize = shown.last().unwrap_or(&1).to_string().len();
+    let pad: String = " ".repeat(width);
+
+    // Header and location
+    let mut header: String = diagnostic.severity.to_string().to_lowercase();
+    if let Some(code) = &diagnostic.code {
+        header.push_str(format!("[{}]", code).as_str());
+    }
+    let mut out: String = format!(
+        "{}{} {}\n",
+        paint(&header, color, severity_style),
+        paint(":", color, |t| t.bold()),
+        paint(&diagnostic.message, color, |t| t.bold())
+    );
+    out.push_str(
+        format!(
+            "{}{} {}:{}:{}\n",
+            pad,
+            paint("-->", color, gutter_style),
+            file_path,
+            diagnostic.span.start.line,
+            diagnostic.span.start.col
+        )
+        .as_str(),
+    );
+    out.push_str(format!("{} {}\n", pad, paint("|", color, gutter_style)).as_str());
+
+    // Source lines, each followed by a marker row per annotation touching it
+    let mut previous: Option<i32> = None;
+    for &line in &shown {
+        if previous.is_some_and(|p| line > p + 1) {
+            out.push_str(format!("{}\n", paint("...", color, gutter_style)).as_str());
+        }
+        previous = Some(line);
+
+        let text: &str = lines.get((line - 1) as usize).copied().unwrap_or("");
+        out.push_str(
+            format!(
+                "{} {} {}\n",
+                paint(&format!("{:>width$}", line), color, gutter_style),
+                paint("|", color, gutter_style),
+                expand_tabs(text)
+            )
+            .as_str(),
+        );
+
+        for annotation in &annotations {
+            let span: Span = annotation.span;
+            if line < span.start.line || line > span.end.line {
+                continue;
+            }
+
+            // Columns of the underline on this line, in chars
+            let line_len: i32 = text.chars().count() as i32;
+            let from: i32 = if line == span.start.line {
+                span.start.col
+            } else {
+                1
+            };
+            let mut to: i32 = if line == span.end.line {
+                span.end.col
+            } else {
+                line_len + 1
+            };
+            if to <= from {
+                to = from + 1;
+            }
+
+            let indent: usize = display_width(text, from);
+            let length: usize = display_width(text, to) - indent;
+            let marker: &str = if annotation.primary { "^" } else { "-" };
+            let mut underline: String = marker.repeat(length);
+            if line == span.end.line
+                && let Some(message) = annotation.message
+            {
+                underline.push(' ');
+                underline.push_str(message);
+            }
+            let underline: String = if annotation.primary {
+                paint(&underline, color, severity_style)
+            } else {
+                paint(&underline, color, gutter_style)
+            };
+            out.push_str(
+                format!(
+                    "{} {} {}{}\n",
+                    pad,
+                    paint("|", color, gutter_style),
+                    " ".repeat(indent),
+                    underline
+                )
+                .as_str(),
+            );
+        }
+    }
+
+    // Notes and help
+    for note in &diagnostic.notes {
+        out.push_str(
+            format!(
+                "{} {} note: {}\n",
+                pad,
+                paint("=", color, gutter_style),
+                note
+            )
+            .as_str(),
+        );
+    }
+    if let Some(help) = &diagnostic.help {
+        out.push_str(
+            format!(
+                "{} {} help: {}\n",
+                pad,
+                paint("=", color, gutter_style),
+                help
+            )
+            .as_str(),
+        );
+    }
+
+    out
+}
+
+/// Utility function to apply a color style to some text, or leave it plain when colors are off
+fn paint(text: &str, color: bool, style: impl Fn(&str) -> ColoredString) -> String {
+    if color {
+        style(text).to_string()
+    } else {
+        text.to_owned()
+    }
+}
+
+/// Utility function to pull back a span that ends just past a newline, so it doesn't underline an empty next line
+fn trim_span(span: Span, lines: &[&str]) -> Span {
+    if span.end.line <= span.start.line || span.end.col > 1 {
+        return span;
+    }
+
+    let line: i32 = span.end.line - 1;
+    let len: usize = lines
+        .get((line - 1) as usize)
+        .map_or(0, |l| l.chars().count());
+    Span::new(
+        span.start,
+        Position::new(line, len as i32 + 1, span.end.offset),
+    )
+}
+
+/// Utility function to replace tabs with spaces, so underlines line up with the printed source line
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', TAB)
+}
+
+/// Utility function to get the printed width of a line up to (not including) a 1-based char column
+fn display_width(text: &str, col: i32) -> usize {
+    let mut width: usize = 0;
+    let mut chars = text.chars();
+    for _ in 1..col {
+        width += match chars.next() {
+            Some('\t') => TAB.len(),
+            _ => 1,
+        };
+    }
+    width
+}
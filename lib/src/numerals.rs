@@ -0,0 +1,104 @@
+// Decimal digit systems
+// ---------------------
+// Every decimal digit system in Unicode (general category Nd, as of Unicode 15.0) is a run of ten
+// code points from zero to nine, so each system can be identified by the code point of its zero.
+
+/// The zero and name of every decimal digit system, in code point order
+const DIGIT_SYSTEMS: &[(u32, &str)] = &[
+    (0x0030, "Western"),
+    (0x0660, "Arabic-Indic"),
+    (0x06F0, "Extended Arabic-Indic"),
+    (0x07C0, "NKo"),
+    (0x0966, "Devanagari"),
+    (0x09E6, "Bengali"),
+    (0x0A66, "Gurmukhi"),
+    (0x0AE6, "Gujarati"),
+    (0x0B66, "Oriya"),
+    (0x0BE6, "Tamil"),
+    (0x0C66, "Telugu"),
+    (0x0CE6, "Kannada"),
+    (0x0D66, "Malayalam"),
+    (0x0DE6, "Sinhala Lith"),
+    (0x0E50, "Thai"),
+    (0x0ED0, "Lao"),
+    (0x0F20, "Tibetan"),
+    (0x1040, "Myanmar"),
+    (0x1090, "Myanmar Shan"),
+    (0x17E0, "Khmer"),
+    (0x1810, "Mongolian"),
+    (0x1946, "Limbu"),
+    (0x19D0, "New Tai Lue"),
+    (0x1A80, "Tai Tham Hora"),
+    (0x1A90, "Tai Tham Tham"),
+    (0x1B50, "Balinese"),
+    (0x1BB0, "Sundanese"),
+    (0x1C40, "Lepcha"),
+    (0x1C50, "Ol Chiki"),
+    (0xA620, "Vai"),
+    (0xA8D0, "Saurashtra"),
+    (0xA900, "Kayah Li"),
+    (0xA9D0, "Javanese"),
+    (0xA9F0, "Myanmar Tai Laing"),
+    (0xAA50, "Cham"),
+    (0xABF0, "Meetei Mayek"),
+    (0xFF10, "Fullwidth"),
+    (0x104A0, "Osmanya"),
+    (0x10D30, "Hanifi Rohingya"),
+    (0x11066, "Brahmi"),
+    (0x110F0, "Sora Sompeng"),
+    (0x11136, "Chakma"),
+    (0x111D0, "Sharada"),
+    (0x112F0, "Khudawadi"),
+    (0x11450, "Newa"),
+    (0x114D0, "Tirhuta"),
+    (0x11650, "Modi"),
+    (0x116C0, "Takri"),
+    (0x11730, "Ahom"),
+    (0x118E0, "Warang Citi"),
+    (0x11950, "Dives Akuru"),
+    (0x11C50, "Bhaiksuki"),
+    (0x11D50, "Masaram Gondi"),
+    (0x11DA0, "Gunjala Gondi"),
+    (0x11F50, "Kawi"),
+    (0x16A60, "Mro"),
+    (0x16AC0, "Tangsa"),
+    (0x16B50, "Pahawh Hmong"),
+    (0x1D7CE, "Mathematical Bold"),
+    (0x1D7D8, "Mathematical Double-Struck"),
+    (0x1D7E2, "Mathematical Sans-Serif"),
+    (0x1D7EC, "Mathematical Sans-Serif Bold"),
+    (0x1D7F6, "Mathematical Monospace"),
+    (0x1E140, "Nyiakeng Puachue Hmong"),
+    (0x1E2F0, "Wancho"),
+    (0x1E4F0, "Nag Mundari"),
+    (0x1E950, "Adlam"),
+    (0x1FBF0, "Segmented"),
+];
+
+/// Get the zero of the decimal digit system a char belongs to, such as ٠ for Arabic-Indic digits.
+/// Returns None if the char isn't a decimal digit
+pub fn digit_zero(c: char) -> Option<char> {
+    let code: u32 = c as u32;
+
+    // Find the last zero at or before the char, and check the char is within its ten digits
+    let i: usize = DIGIT_SYSTEMS.partition_point(|&(zero, _)| zero <= code);
+    let (zero, _): (u32, &str) = *DIGIT_SYSTEMS.get(i.checked_sub(1)?)?;
+    if code - zero < 10 {
+        char::from_u32(zero)
+    } else {
+        None
+    }
+}
+
+/// Get the value of a decimal digit from any digit system
+pub fn digit_value(c: char) -> Option<u32> {
+    digit_zero(c).map(|zero| c as u32 - zero as u32)
+}
+
+/// Get the name of the digit system with the given zero, for messages
+pub fn digit_system_name(zero: char) -> &'static str {
+    DIGIT_SYSTEMS
+        .iter()
+        .find(|&&(z, _)| z == zero as u32)
+        .map_or("Unknown", |&(_, name)| name)
+}
@@ -0,0 +1,246 @@
+use std::sync::LazyLock;
+
+use unicode_xid::UnicodeXID;
+
+use crate::token::TokenType;
+
+/// An operator, as written in source code
+pub struct Operator {
+    pub symbol: &'static str,      // how the operator is spelled
+    pub token_type: TokenType,     // the token it scans as
+    pub description: &'static str, // what it's used for, shown in the generated docs
+}
+
+/// Utility function to write an entry of the operator table on one line
+const fn op(symbol: &'static str, token_type: TokenType, description: &'static str) -> Operator {
+    Operator {
+        symbol,
+        token_type,
+        description,
+    }
+}
+
+/// Every operator in Rumil. This table is the single source of truth for operators: the lexer's trie,
+/// docs/syntax/operators.md, and the operator tests are all generated from it
+pub const OPERATORS: &[Operator] = &[
+    // Punctuation
+    op(
+        "(",
+        TokenType::LeftParen,
+        "Open a group, call, or parameter list",
+    ),
+    op(
+        ")",
+        TokenType::RightParen,
+        "Close a group, call, or parameter list",
+    ),
+    op("[", TokenType::LeftBracket, "Open an array or index"),
+    op("]", TokenType::RightBracket, "Close an array or index"),
+    op("{", TokenType::LeftBrace, "Open a block"),
+    op("}", TokenType::RightBrace, "Close a block"),
+    op(",", TokenType::Comma, "Separate items in a list"),
+    op(":", TokenType::Colon, "Annotate a type"),
+    op("::", TokenType::ColonColon, "Separate parts of a path"),
+    op("->", TokenType::RightArrow, "Give a function's return type"),
+    op(
+        "=>",
+        TokenType::EqualsArrow,
+        "Separate a match arm's pattern from its body",
+    ),
+    op("_", TokenType::Underscore, "Match anything"),
+    op("?", TokenType::Question, "Match a value against arms"),
+    op("@", TokenType::At, "Loop"),
+    op("$", TokenType::Dollar, "Print"),
+    op("#", TokenType::Hash, "Length of an array or string"),
+    // Access
+    op(".", TokenType::Dot, "Access a field"),
+    op(".?", TokenType::DotQuestion, "Access an optional field"),
+    op(
+        "..?",
+        TokenType::DotDotQuestion,
+        "Fall back to the right side when the left is missing",
+    ),
+    // Binding and assignment
+    op(":=", TokenType::ColonEquals, "Bind a name"),
+    op("=", TokenType::Equals, "Assign"),
+    op("+=", TokenType::PlusEquals, "Add and assign"),
+    op("-=", TokenType::MinusEquals, "Subtract and assign"),
+    op("*=", TokenType::StarEquals, "Multiply and assign"),
+    op("/=", TokenType::SlashEquals, "Divide and assign"),
+    op(
+        "%=",
+        TokenType::PercentEquals,
+        "Take the remainder and assign",
+    ),
+    op("&=", TokenType::AndEquals, "Bitwise and, and assign"),
+    op("^=", TokenType::CaretEquals, "Bitwise xor, and assign"),
+    op("<<=", TokenType::LeftShiftEquals, "Shift left and assign"),
+    op(">>=", TokenType::RightShiftEquals, "Shift right and assign"),
+    op("<-", TokenType::LeftArrow, "Send"),
+    // Arithmetic
+    op("+", TokenType::Plus, "Add"),
+    op("-", TokenType::Minus, "Subtract or negate"),
+    op("*", TokenType::Star, "Multiply"),
+    op("/", TokenType::Slash, "Divide"),
+    op("%", TokenType::Percent, "Remainder"),
+    // Bitwise
+    op("&", TokenType::And, "Bitwise and"),
+    op("|", TokenType::Pipe, "Bitwise or"),
+    op("^", TokenType::Caret, "Bitwise xor"),
+    op("~", TokenType::Tilde, "Bitwise not"),
+    op("<<", TokenType::LeftShift, "Shift left"),
+    op(">>", TokenType::RightShift, "Shift right"),
+    // Comparison
+    op("==", TokenType::EqualsEquals, "Equal"),
+    op("!=", TokenType::BangEquals, "Not equal"),
+    op("~=", TokenType::TildeEquals, "Match a pattern"),
+    op("<", TokenType::LeftAngle, "Less than"),
+    op("<=", TokenType::LessOrEquals, "Less than or equal"),
+    op(">", TokenType::RightAngle, "Greater than"),
+    op(">=", TokenType::GreaterOrEquals, "Greater than or equal"),
+    // Logic
+    op("!", TokenType::Bang, "Not"),
+    op("&&", TokenType::AndAnd, "And"),
+    op("||", TokenType::PipePipe, "Or"),
+];
+
+/// The standard operators, compiled once and shared by every Lexer that isn't given its own
+pub static STANDARD: LazyLock<Operators> = LazyLock::new(Operators::standard);
+
+/// A set of operators compiled into a trie over their bytes, so the longest operator at any point in the
+/// source code is found in a single pass, however many operators there are or however long they get
+#[derive(Clone)]
+pub struct Operators {
+    nodes: Vec<TrieNode>, // the root is the first node
+}
+
+/// A node of the operator trie, reached by the bytes of an operator's symbol so far
+#[derive(Clone, Default)]
+struct TrieNode {
+    children: Vec<(u8, usize)>, // each byte that can come next, with the index of the node it leads to
+    operator: Option<TokenType>, // the operator whose symbol ends here, if any
+}
+
+impl Operators {
+    /// Create an empty set of operators
+    pub fn new() -> Operators {
+        Operators {
+            nodes: vec![TrieNode::default()],
+        }
+    }
+
+    /// Create the set of standard Rumil operators, from the operator table
+    pub fn standard() -> Operators {
+        let mut operators: Operators = Operators::new();
+        for operator in OPERATORS {
+            operators.insert(operator.symbol, operator.token_type);
+        }
+        operators
+    }
+
+    /// Register an extra operator, such as a dialect's own spelling of a standard one. Operators can't
+    /// start with chars that begin other tokens, like letters, digits, quotes, or ; for comments, can't
+    /// contain whitespace, and can't change an operator that's already registered
+    pub fn register(&mut self, symbol: &str, token_type: TokenType) -> Result<(), String> {
+        let Some(first) = symbol.chars().next() else {
+            return Err("Operators can't be empty".to_owned());
+        };
+        if first.is_xid_continue() || matches!(first, '"' | '\'' | '`' | ';') {
+            return Err(format!(
+                "Operator [{}] can't start with [{}]",
+                symbol, first
+            ));
+        }
+        if symbol.chars().any(char::is_whitespace) {
+            return Err(format!("Operator [{}] can't contain whitespace", symbol));
+        }
+
+        match self.get(symbol) {
+            Some(existing) if existing == token_type => Ok(()),
+            Some(existing) => Err(format!(
+                "Operator [{}] is already registered as {}",
+                symbol, existing
+            )),
+            None => {
+                self.insert(symbol, token_type);
+                Ok(())
+            }
+        }
+    }
+
+    /// Get the operator spelled exactly as the given symbol
+    pub fn get(&self, symbol: &str) -> Option<TokenType> {
+        let mut node: usize = 0;
+        for byte in symbol.bytes() {
+            node = self.child(node, byte)?;
+        }
+        self.nodes[node].operator
+    }
+
+    /// Find the longest operator at the start of the given text, along with its length in bytes
+    pub fn longest_match(&self, text: &str) -> Option<(TokenType, usize)> {
+        let mut node: usize = 0;
+        let mut longest: Option<(TokenType, usize)> = None;
+        for (i, byte) in text.bytes().enumerate() {
+            match self.child(node, byte) {
+                Some(child) => node = child,
+                None => break,
+            }
+            if let Some(operator) = self.nodes[node].operator {
+                longest = Some((operator, i + 1));
+            }
+        }
+        longest
+    }
+
+    /// Add an operator to the trie, replacing whatever was spelled the same way
+    fn insert(&mut self, symbol: &str, token_type: TokenType) {
+        let mut node: usize = 0;
+        for byte in symbol.bytes() {
+            node = match self.child(node, byte) {
+                Some(child) => child,
+                None => {
+                    self.nodes.push(TrieNode::default());
+                    let child: usize = self.nodes.len() - 1;
+                    self.nodes[node].children.push((byte, child));
+                    child
+                }
+            };
+        }
+        self.nodes[node].operator = Some(token_type);
+    }
+
+    /// Utility function to follow a byte from a node of the trie
+    fn child(&self, node: usize, byte: u8) -> Option<usize> {
+        self.nodes[node]
+            .children
+            .iter()
+            .find(|(b, _)| *b == byte)
+            .map(|(_, child)| *child)
+    }
+}
+
+impl Default for Operators {
+    fn default() -> Self {
+        Operators::new()
+    }
+}
+
+/// Generate the markdown reference of every operator in the operator table, as found in
+/// docs/syntax/operators.md
+pub fn markdown_reference() -> String {
+    let mut doc: String = String::from(
+        "# Operators\n\n\
+         <!-- Generated from lib/src/operators.rs. Regenerate with `RUMIL_UPDATE_DOCS=1 cargo test` -->\n\n\
+         | Operator | Token | Meaning |\n\
+         |----------|-------|---------|\n",
+    );
+    for operator in OPERATORS {
+        let symbol: String = operator.symbol.replace('|', "\\|");
+        doc.push_str(&format!(
+            "| `{}` | {} | {} |\n",
+            symbol, operator.token_type, operator.description
+        ));
+    }
+    doc
+}
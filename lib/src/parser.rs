@@ -0,0 +1,1188 @@
+use crate::{
+    ast::{
+        AssignOp, BinaryOp, Binding, Block, Expr, ExprKind, Function, Ident, InterpolationPart,
+        Item, ItemKind, Literal, MatchArm, Param, Pattern, PatternKind, Stmt, StmtKind, Type,
+        TypeKind, UnaryOp,
+    },
+    codes,
+    diagnostic::{Diagnostic, Diagnostics},
+    log::{debugging, log_debug},
+    operators::OPERATORS,
+    span::{Position, Span},
+    token::{FormSegment, LiteralValue, Token, TokenType, TriviaKind},
+};
+
+// Binding powers for Pratt parsing, from loosest to tightest
+pub(crate) const BP_MATCH: u8 = 1;
+const BP_COALESCE: u8 = 2;
+const BP_OR: u8 = 3;
+const BP_AND: u8 = 4;
+const BP_COMPARISON: u8 = 5;
+const BP_BIT_OR: u8 = 6;
+const BP_BIT_XOR: u8 = 7;
+const BP_BIT_AND: u8 = 8;
+const BP_SHIFT: u8 = 9;
+const BP_SUM: u8 = 10;
+const BP_PRODUCT: u8 = 11;
+pub(crate) const BP_PREFIX: u8 = 12;
+
+// Syntax errors are boxed to keep results small, since they are rare
+type ParseResult<T> = Result<T, Box<Diagnostic>>;
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,    // the tokens to parse, without comments
+    pos: usize,                // index of the token we're currently looking at
+    docs: Vec<Token<'a>>,      // doc comments, to be attached to the items after them
+    doc_pos: usize,            // index of the first doc comment not yet attached or reported
+    diagnostics: Diagnostics,  // syntax errors and warnings found while parsing
+    last_error: Option<usize>, // where the last syntax error was reported, so it's only reported once
+    unclosed: bool,            // whether a block or match ran on to the end of the tokens
+}
+
+impl<'a> Parser<'a> {
+    /// Create a new Parser. The tokens can be scanned losslessly, in which case doc comments are
+    /// found in their trivia instead
+    fn new(tokens: Vec<Token<'a>>) -> Parser<'a> {
+        let mut kept: Vec<Token<'a>> = Vec::new();
+        let mut docs: Vec<Token<'a>> = Vec::new();
+        for tk in tokens {
+            for trivia in tk.leading.iter().chain(&tk.trailing) {
+                if trivia.kind == TriviaKind::DocComment {
+                    docs.push(Token::new(trivia.text, TokenType::DocComment, trivia.span));
+                }
+            }
+
+            match tk.token_type {
+                TokenType::Comment | TokenType::BlockComment | TokenType::EOF => {}
+                TokenType::DocComment => docs.push(tk),
+                _ => kept.push(tk),
+            }
+        }
+
+        Parser {
+            tokens: kept,
+            pos: 0,
+            docs,
+            doc_pos: 0,
+            diagnostics: Diagnostics::new(),
+            last_error: None,
+            unclosed: false,
+        }
+    }
+
+    /// Parse items until the end of the token stream
+    fn parse_program(&mut self) -> Vec<Item> {
+        let mut items: Vec<Item> = Vec::new();
+        while !self.at_end() {
+            items.push(self.parse_item());
+        }
+
+        // Doc comments after the last item have nothing to document
+        while self.doc_pos < self.docs.len() {
+            self.warn_unattached_doc();
+        }
+
+        items
+    }
+
+    // Items
+    // -----
+
+    /// Parse a top-level item. Bindings of a function literal to a plain name become named functions
+    fn parse_item(&mut self) -> Item {
+        let docs: Vec<String> = self.take_docs();
+        let stmt: Stmt = self.parse_statement_or_error();
+        let span: Span = stmt.span;
+
+        let kind: ItemKind = match stmt.kind {
+            StmtKind::Binding(binding) => match binding {
+                Binding {
+                    pattern:
+                        Pattern {
+                            kind: PatternKind::Identifier(name),
+                            span: name_span,
+                        },
+                    ty: None,
+                    value:
+                        Expr {
+                            kind: ExprKind::Function(function),
+                            ..
+                        },
+                    ..
+                } => ItemKind::Function {
+                    name: Ident {
+                        name,
+                        span: name_span,
+                    },
+                    function: *function,
+                },
+                binding => ItemKind::Binding(binding),
+            },
+            kind => ItemKind::Stmt(Stmt { kind, span }),
+        };
+
+        Item { kind, docs, span }
+    }
+
+    /// Take the doc comments written between the last consumed token and the current one. Doc comments
+    /// skipped over before that, such as ones inside the previous item, are reported as unattached
+    fn take_docs(&mut self) -> Vec<String> {
+        let after: usize = if self.pos == 0 {
+            0
+        } else {
+            self.prev_end().offset
+        };
+        let before: usize = self.start().offset;
+
+        let mut docs: Vec<String> = Vec::new();
+        while let Some(doc) = self.docs.get(self.doc_pos) {
+            if doc.span.start.offset >= before {
+                break;
+            }
+            if doc.span.start.offset < after {
+                self.warn_unattached_doc();
+                continue;
+            }
+
+            docs.push(doc_text(doc.value));
+            self.doc_pos += 1;
+        }
+
+        docs
+    }
+
+    /// Warn that the next doc comment isn't attached to any item, and move past it
+    fn warn_unattached_doc(&mut self) {
+        let span: Span = self.docs[self.doc_pos].span;
+        self.doc_pos += 1;
+        self.diagnostics.push(
+            Diagnostic::warning("Doc comment isn't attached to anything".to_owned(), span)
+                .with_code(codes::UNATTACHED_DOC_COMMENT)
+                .with_help(
+                    "Move it above a top-level item, or use [;] for a plain comment".to_owned(),
+                ),
+        );
+    }
+
+    /// Parse a function literal; the current token must be the opening paren of its parameters
+    fn parse_function(&mut self) -> ParseResult<Function> {
+        let start: Position = self.start();
+        let (params, _) = self.parse_list(
+            TokenType::LeftParen,
+            TokenType::RightParen,
+            Self::parse_param,
+        )?;
+        self.expect(TokenType::RightArrow)?;
+
+        let return_type: Option<Type> = if self.check(TokenType::LeftBrace) {
+            None
+        } else {
+            Some(self.parse_type()?)
+        };
+
+        let body: Block = self.parse_block()?;
+        Ok(Function {
+            params,
+            return_type,
+            body,
+            span: self.finish(start),
+        })
+    }
+
+    /// Parse a function parameter: name or name: Type
+    fn parse_param(&mut self) -> ParseResult<Param> {
+        let start: Position = self.start();
+        let name: Ident = self.parse_ident()?;
+
+        let mut ty: Option<Type> = None;
+        if self.check(TokenType::Colon) {
+            self.advance();
+            ty = Some(self.parse_type()?);
+        }
+
+        Ok(Param {
+            name,
+            ty,
+            span: self.finish(start),
+        })
+    }
+
+    /// Parse a name
+    fn parse_ident(&mut self) -> ParseResult<Ident> {
+        let tk: &Token = self.expect(TokenType::Identifier)?;
+        Ok(Ident {
+            name: tk.name().into_owned(),
+            span: tk.span,
+        })
+    }
+
+    // Statements
+    // ----------
+
+    /// Parse a statement, or if it has a syntax error, report it and skip the rest of it, leaving an error
+    /// node in its place. At least one token is always consumed, so a stray token can't stall the parser
+    fn parse_statement_or_error(&mut self) -> Stmt {
+        let start: Position = self.start();
+        let start_pos: usize = self.pos;
+        match self.parse_statement() {
+            Ok(stmt) => stmt,
+            Err(diagnostic) => {
+                self.recover(*diagnostic, &[]);
+                if self.pos == start_pos {
+                    self.advance();
+                }
+
+                let span: Span = self.finish(start);
+                Stmt {
+                    kind: StmtKind::Expr(Expr {
+                        kind: ExprKind::Error,
+                        span,
+                    }),
+                    span,
+                }
+            }
+        }
+    }
+
+    /// Parse a single statement: a return, a binding, an assignment or an expression
+    fn parse_statement(&mut self) -> ParseResult<Stmt> {
+        let start: Position = self.start();
+
+        // -> value
+        if self.check(TokenType::RightArrow) {
+            self.advance();
+
+            let mut value: Option<Expr> = None;
+            if !self.at_end() && !self.check(TokenType::RightBrace) && self.same_line() {
+                value = Some(self.parse_expression_or_error(&[]));
+            }
+
+            return Ok(Stmt {
+                kind: StmtKind::Return(value),
+                span: self.finish(start),
+            });
+        }
+
+        // pattern := value, pattern: Type := value
+        if let Some(binding) = self.try_parse_binding()? {
+            return Ok(Stmt {
+                kind: StmtKind::Binding(binding),
+                span: self.finish(start),
+            });
+        }
+
+        let expr: Expr = self.parse_expression(0)?;
+
+        // place = value, place += value, etc.
+        if let Some(op) = self.peek().and_then(|tk| assign_op(&tk.token_type)) {
+            if !is_place(&expr) {
+                return Err(Box::new(
+                    Diagnostic::error(format!("Cannot assign to [{}]", expr), expr.span)
+                        .with_code(codes::INVALID_ASSIGNMENT)
+                        .with_help(
+                            "Only names, fields, indexes and paths can be assigned to".to_owned(),
+                        ),
+                ));
+            }
+
+            self.advance();
+            let value: Expr = self.parse_expression_or_error(&[]);
+            return Ok(Stmt {
+                kind: StmtKind::Assign {
+                    target: expr,
+                    op,
+                    value,
+                },
+                span: self.finish(start),
+            });
+        }
+
+        Ok(Stmt {
+            span: expr.span,
+            kind: StmtKind::Expr(expr),
+        })
+    }
+
+    /// Parse a binding if the upcoming tokens are a pattern followed by := or a type annotation.
+    /// Otherwise rewind and return nothing so the tokens can be parsed as an expression
+    fn try_parse_binding(&mut self) -> ParseResult<Option<Binding>> {
+        let start: Position = self.start();
+        let rewind: usize = self.pos;
+
+        let pattern: Pattern = match self.parse_pattern() {
+            Ok(pattern) if self.check(TokenType::ColonEquals) || self.check(TokenType::Colon) => {
+                pattern
+            }
+            _ => {
+                self.pos = rewind;
+                return Ok(None);
+            }
+        };
+
+        let mut ty: Option<Type> = None;
+        if self.check(TokenType::Colon) {
+            self.advance();
+            ty = Some(self.parse_type()?);
+        }
+
+        self.expect(TokenType::ColonEquals)?;
+        let value: Expr = self.parse_expression_or_error(&[]);
+
+        Ok(Some(Binding {
+            pattern,
+            ty,
+            value,
+            span: self.finish(start),
+        }))
+    }
+
+    /// Parse a block of statements; the current token must be the opening brace.
+    /// An unclosed block is reported, and runs on to the end of the file
+    fn parse_block(&mut self) -> ParseResult<Block> {
+        let start: Position = self.start();
+        let open: Span = self.expect(TokenType::LeftBrace)?.span;
+
+        let mut statements: Vec<Stmt> = Vec::new();
+        while !self.check(TokenType::RightBrace) {
+            if self.at_end() {
+                self.unclosed = true;
+                let diagnostic: Diagnostic = *self.error_here(
+                    codes::UNCLOSED_BLOCK,
+                    "Unclosed block, expected [}]".to_owned(),
+                );
+                self.report(diagnostic.with_label(open, "Block opened here".to_owned()));
+                return Ok(Block {
+                    statements,
+                    span: self.finish(start),
+                });
+            }
+            statements.push(self.parse_statement_or_error());
+        }
+
+        self.advance();
+        Ok(Block {
+            statements,
+            span: self.finish(start),
+        })
+    }
+
+    // Expressions
+    // -----------
+
+    /// Parse an expression whose binary operators bind tighter than min_bp
+    fn parse_expression(&mut self, min_bp: u8) -> ParseResult<Expr> {
+        let mut left: Expr = self.parse_prefix()?;
+
+        while let Some(tk) = self.peek() {
+            // Operators must be on the same line as their left operand, otherwise they start a new statement
+            if !self.same_line() {
+                break;
+            }
+
+            // subject ? { arms }
+            if tk.token_type == TokenType::Question {
+                if BP_MATCH <= min_bp {
+                    break;
+                }
+
+                self.advance();
+                let arms: Vec<MatchArm> = self.parse_match_arms()?;
+                let span: Span = self.finish(left.span.start);
+                left = Expr {
+                    kind: ExprKind::Match {
+                        subject: Box::new(left),
+                        arms,
+                    },
+                    span,
+                };
+                continue;
+            }
+
+            let Some((op, bp, right_assoc)) = binary_op(&tk.token_type) else {
+                break;
+            };
+            if bp <= min_bp {
+                break;
+            }
+
+            self.advance();
+            let next_bp = if right_assoc { bp - 1 } else { bp };
+            let right: Expr = self.parse_expression(next_bp)?;
+            let span: Span = left.span.to(right.span);
+            left = Expr {
+                kind: ExprKind::Binary {
+                    op,
+                    left: Box::new(left),
+                    right: Box::new(right),
+                },
+                span,
+            };
+        }
+
+        Ok(left)
+    }
+
+    /// Parse an expression, or if it has a syntax error, report it and skip to the end of the line, a closing
+    /// brace, or one of the given tokens, leaving an error node in its place
+    fn parse_expression_or_error(&mut self, stops: &[TokenType]) -> Expr {
+        let start: Position = self.start();
+        let start_pos: usize = self.pos;
+        match self.parse_expression(0) {
+            Ok(expr) => expr,
+            Err(diagnostic) => {
+                self.recover(*diagnostic, stops);
+                let span: Span = if self.pos == start_pos {
+                    Span::new(start, start)
+                } else {
+                    self.finish(start)
+                };
+                Expr {
+                    kind: ExprKind::Error,
+                    span,
+                }
+            }
+        }
+    }
+
+    /// Parse the braced arms of a match expression: { pattern => value, ... }. Arms with a syntax error in
+    /// their pattern are reported and left out, and an unclosed match is reported and runs on to the end of the file
+    fn parse_match_arms(&mut self) -> ParseResult<Vec<MatchArm>> {
+        let open: Span = self.expect(TokenType::LeftBrace)?.span;
+
+        let mut arms: Vec<MatchArm> = Vec::new();
+        while !self.check(TokenType::RightBrace) {
+            if self.at_end() {
+                self.unclosed = true;
+                let diagnostic: Diagnostic = *self.error_here(
+                    codes::UNCLOSED_MATCH,
+                    "Unclosed match, expected [}]".to_owned(),
+                );
+                self.report(diagnostic.with_label(open, "Match opened here".to_owned()));
+                return Ok(arms);
+            }
+
+            let start_pos: usize = self.pos;
+            match self.parse_match_arm() {
+                Ok(arm) => arms.push(arm),
+                Err(diagnostic) => {
+                    self.recover(*diagnostic, &[TokenType::Comma]);
+                    if self.pos == start_pos && !self.check(TokenType::Comma) {
+                        self.advance();
+                    }
+                }
+            }
+
+            // Arms are separated by commas or new lines
+            if self.check(TokenType::Comma) {
+                self.advance();
+            } else if !self.check(TokenType::RightBrace) && self.same_line() {
+                let diagnostic: Diagnostic = *self.error_here(
+                    codes::MISSING_ARM_SEPARATOR,
+                    "Expected [,] or [}] after match arm".to_owned(),
+                );
+                self.report(diagnostic);
+            }
+        }
+
+        self.advance();
+        Ok(arms)
+    }
+
+    /// Parse a single match arm: pattern => value
+    fn parse_match_arm(&mut self) -> ParseResult<MatchArm> {
+        let start: Position = self.start();
+        let pattern: Pattern = self.parse_pattern()?;
+        self.expect(TokenType::EqualsArrow)?;
+        let value: Expr = self.parse_expression_or_error(&[TokenType::Comma]);
+        Ok(MatchArm {
+            pattern,
+            value,
+            span: self.finish(start),
+        })
+    }
+
+    /// Parse a prefix operator applied to an operand, a loop, or a postfix expression
+    fn parse_prefix(&mut self) -> ParseResult<Expr> {
+        let start: Position = self.start();
+
+        // @ condition { body }
+        if self.check(TokenType::At) {
+            self.advance();
+
+            let mut condition: Option<Box<Expr>> = None;
+            if !self.check(TokenType::LeftBrace) {
+                condition = Some(Box::new(self.parse_expression(0)?));
+            }
+
+            let body: Block = self.parse_block()?;
+            return Ok(Expr {
+                kind: ExprKind::Loop { condition, body },
+                span: self.finish(start),
+            });
+        }
+
+        let op: Option<UnaryOp> = match self.peek().map(|tk| &tk.token_type) {
+            Some(TokenType::Bang) => Some(UnaryOp::Not),
+            Some(TokenType::Minus) => Some(UnaryOp::Negate),
+            Some(TokenType::Tilde) => Some(UnaryOp::BitNot),
+            Some(TokenType::Hash) => Some(UnaryOp::Length),
+            _ => None,
+        };
+
+        match op {
+            Some(op) => {
+                self.advance();
+                let operand: Expr = self.parse_expression(BP_PREFIX)?;
+                Ok(Expr {
+                    kind: ExprKind::Unary {
+                        op,
+                        operand: Box::new(operand),
+                    },
+                    span: self.finish(start),
+                })
+            }
+            None => self.parse_postfix(),
+        }
+    }
+
+    /// Parse a primary expression followed by any calls, indexes, field accesses or paths
+    fn parse_postfix(&mut self) -> ParseResult<Expr> {
+        let start: Position = self.start();
+        let mut expr: Expr = self.parse_primary()?;
+
+        while let Some(tk) = self.peek() {
+            let kind: ExprKind = match tk.token_type {
+                // Calls and indexes must open on the same line, otherwise they start a new statement
+                TokenType::LeftParen if self.same_line() => {
+                    let (args, _) =
+                        self.parse_list(TokenType::LeftParen, TokenType::RightParen, |p| {
+                            Ok(p.parse_expression_or_error(&[
+                                TokenType::Comma,
+                                TokenType::RightParen,
+                            ]))
+                        })?;
+                    ExprKind::Call {
+                        callee: Box::new(expr),
+                        args,
+                    }
+                }
+                TokenType::LeftBracket if self.same_line() => {
+                    self.advance();
+                    let index: Expr = self.parse_expression_or_error(&[TokenType::RightBracket]);
+                    self.expect(TokenType::RightBracket)?;
+                    ExprKind::Index {
+                        target: Box::new(expr),
+                        index: Box::new(index),
+                    }
+                }
+                TokenType::Dot | TokenType::DotQuestion => {
+                    let optional = tk.token_type == TokenType::DotQuestion;
+                    self.advance();
+                    ExprKind::Field {
+                        target: Box::new(expr),
+                        name: self.parse_ident()?,
+                        optional,
+                    }
+                }
+                TokenType::ColonColon => {
+                    self.advance();
+                    ExprKind::Path {
+                        target: Box::new(expr),
+                        name: self.parse_ident()?,
+                    }
+                }
+                _ => break,
+            };
+
+            expr = Expr {
+                kind,
+                span: self.finish(start),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    /// Parse a literal, a name, a grouping, a tuple, an array, a block or a function literal
+    fn parse_primary(&mut self) -> ParseResult<Expr> {
+        let start: Position = self.start();
+        let Some(tk) = self.peek() else {
+            return Err(self.error_here(
+                codes::EXPECTED_EXPRESSION,
+                "Expected an expression".to_owned(),
+            ));
+        };
+
+        let kind: ExprKind = match tk.token_type {
+            TokenType::Identifier => ExprKind::Identifier(tk.name().into_owned()),
+            TokenType::Dollar => ExprKind::Print,
+
+            // The lexer has already reported the error, so it stands in for the expression it should have been
+            TokenType::Error => ExprKind::Error,
+
+            TokenType::LeftParen if self.function_ahead() => {
+                let function: Function = self.parse_function()?;
+                return Ok(Expr {
+                    span: function.span,
+                    kind: ExprKind::Function(Box::new(function)),
+                });
+            }
+            TokenType::LeftParen => {
+                let (mut items, trailing_comma) =
+                    self.parse_list(TokenType::LeftParen, TokenType::RightParen, |p| {
+                        Ok(p.parse_expression_or_error(&[TokenType::Comma, TokenType::RightParen]))
+                    })?;
+
+                // A single parenthesized expression without a trailing comma is just a grouping
+                if items.len() == 1 && !trailing_comma {
+                    let mut expr: Expr = items.remove(0);
+                    expr.span = self.finish(start);
+                    return Ok(expr);
+                }
+                return Ok(Expr {
+                    kind: ExprKind::Tuple(items),
+                    span: self.finish(start),
+                });
+            }
+            TokenType::LeftBracket => {
+                let (items, _) =
+                    self.parse_list(TokenType::LeftBracket, TokenType::RightBracket, |p| {
+                        Ok(p.parse_expression_or_error(&[
+                            TokenType::Comma,
+                            TokenType::RightBracket,
+                        ]))
+                    })?;
+                return Ok(Expr {
+                    kind: ExprKind::Array(items),
+                    span: self.finish(start),
+                });
+            }
+            TokenType::LeftBrace => {
+                let block: Block = self.parse_block()?;
+                return Ok(Expr {
+                    span: block.span,
+                    kind: ExprKind::Block(block),
+                });
+            }
+            TokenType::FormString => {
+                // Indexed directly, since tk borrows the whole parser
+                let parts: Vec<InterpolationPart> = match &self.tokens[self.pos].literal {
+                    Some(LiteralValue::Form(segments)) => {
+                        parse_interpolation(segments, &mut self.diagnostics)
+                    }
+                    _ => Vec::new(),
+                };
+                ExprKind::Interpolation(parts)
+            }
+
+            _ => match literal(tk) {
+                Some(literal) => ExprKind::Literal(literal),
+                None => {
+                    return Err(self.error_here(
+                        codes::EXPECTED_EXPRESSION,
+                        "Expected an expression".to_owned(),
+                    ));
+                }
+            },
+        };
+
+        self.advance();
+        Ok(Expr {
+            kind,
+            span: self.finish(start),
+        })
+    }
+
+    /// Check if the parenthesized list starting at the current token is followed by ->,
+    /// which makes it the parameter list of a function literal
+    fn function_ahead(&self) -> bool {
+        let mut depth = 0;
+        for (i, tk) in self.tokens.iter().enumerate().skip(self.pos) {
+            match tk.token_type {
+                TokenType::LeftParen => depth += 1,
+                TokenType::RightParen => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return self
+                            .tokens
+                            .get(i + 1)
+                            .is_some_and(|next| next.token_type == TokenType::RightArrow);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        false
+    }
+
+    /// Parse a comma separated list of nodes between the open and close tokens.
+    /// A trailing comma is allowed, and whether there was one is returned alongside the nodes
+    fn parse_list<T>(
+        &mut self,
+        open: TokenType,
+        close: TokenType,
+        parse_node: impl Fn(&mut Self) -> ParseResult<T>,
+    ) -> ParseResult<(Vec<T>, bool)> {
+        self.expect(open)?;
+
+        let mut nodes: Vec<T> = Vec::new();
+        let mut trailing_comma = false;
+        while !self.check(close) {
+            nodes.push(parse_node(self)?);
+
+            trailing_comma = self.check(TokenType::Comma);
+            if !trailing_comma {
+                break;
+            }
+            self.advance();
+        }
+
+        self.expect(close)?;
+        Ok((nodes, trailing_comma))
+    }
+
+    // Patterns
+    // --------
+
+    /// Parse a pattern: _, a name, a literal, or a tuple or array of patterns
+    fn parse_pattern(&mut self) -> ParseResult<Pattern> {
+        let start: Position = self.start();
+        let Some(tk) = self.peek() else {
+            return Err(self.error_here(codes::EXPECTED_PATTERN, "Expected a pattern".to_owned()));
+        };
+
+        let kind: PatternKind = match tk.token_type {
+            TokenType::Underscore => PatternKind::Wildcard,
+            TokenType::Identifier => PatternKind::Identifier(tk.name().into_owned()),
+
+            // Negative number literals
+            TokenType::Minus => {
+                self.advance();
+                let literal: Literal = match self.peek().and_then(literal) {
+                    Some(literal @ (Literal::Int(..) | Literal::Float(..))) => literal,
+                    _ => {
+                        return Err(self.error_here(
+                            codes::EXPECTED_PATTERN,
+                            "Expected a number literal".to_owned(),
+                        ));
+                    }
+                };
+                PatternKind::Literal {
+                    literal,
+                    negative: true,
+                }
+            }
+
+            TokenType::LeftParen => {
+                let (mut items, trailing_comma) = self.parse_list(
+                    TokenType::LeftParen,
+                    TokenType::RightParen,
+                    Self::parse_pattern,
+                )?;
+
+                if items.len() == 1 && !trailing_comma {
+                    let mut pattern: Pattern = items.remove(0);
+                    pattern.span = self.finish(start);
+                    return Ok(pattern);
+                }
+                return Ok(Pattern {
+                    kind: PatternKind::Tuple(items),
+                    span: self.finish(start),
+                });
+            }
+            TokenType::LeftBracket => {
+                let (items, _) = self.parse_list(
+                    TokenType::LeftBracket,
+                    TokenType::RightBracket,
+                    Self::parse_pattern,
+                )?;
+                return Ok(Pattern {
+                    kind: PatternKind::Array(items),
+                    span: self.finish(start),
+                });
+            }
+
+            _ => match literal(tk) {
+                Some(literal) => PatternKind::Literal {
+                    literal,
+                    negative: false,
+                },
+                None => {
+                    return Err(
+                        self.error_here(codes::EXPECTED_PATTERN, "Expected a pattern".to_owned())
+                    );
+                }
+            },
+        };
+
+        self.advance();
+        Ok(Pattern {
+            kind,
+            span: self.finish(start),
+        })
+    }
+
+    // Types
+    // -----
+
+    /// Parse a type annotation
+    fn parse_type(&mut self) -> ParseResult<Type> {
+        let start: Position = self.start();
+        let Some(tk) = self.peek() else {
+            return Err(self.error_here(codes::EXPECTED_TYPE, "Expected a type".to_owned()));
+        };
+
+        let kind: TypeKind = match tk.token_type {
+            // ?Type
+            TokenType::Question => {
+                self.advance();
+                TypeKind::Optional(Box::new(self.parse_type()?))
+            }
+
+            // [Type]
+            TokenType::LeftBracket => {
+                self.advance();
+                let ty: Type = self.parse_type()?;
+                self.expect(TokenType::RightBracket)?;
+                TypeKind::Array(Box::new(ty))
+            }
+
+            // (Type, Type) -> Type, (Type, Type) or (Type)
+            TokenType::LeftParen => {
+                let (mut items, trailing_comma) = self.parse_list(
+                    TokenType::LeftParen,
+                    TokenType::RightParen,
+                    Self::parse_type,
+                )?;
+
+                if self.check(TokenType::RightArrow) {
+                    self.advance();
+                    TypeKind::Function {
+                        params: items,
+                        ret: Box::new(self.parse_type()?),
+                    }
+                } else if items.len() == 1 && !trailing_comma {
+                    let mut ty: Type = items.remove(0);
+                    ty.span = self.finish(start);
+                    return Ok(ty);
+                } else {
+                    TypeKind::Tuple(items)
+                }
+            }
+
+            // name::name
+            TokenType::Identifier => {
+                let mut path: Vec<Ident> = vec![self.parse_ident()?];
+                while self.check(TokenType::ColonColon) {
+                    self.advance();
+                    path.push(self.parse_ident()?);
+                }
+                TypeKind::Named(path)
+            }
+
+            _ => return Err(self.error_here(codes::EXPECTED_TYPE, "Expected a type".to_owned())),
+        };
+
+        Ok(Type {
+            kind,
+            span: self.finish(start),
+        })
+    }
+
+    // Token navigation
+    // ----------------
+
+    /// Look at the current token without consuming it
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos)
+    }
+
+    /// Consume the current token and return it
+    fn advance(&mut self) -> &Token<'a> {
+        self.pos += 1;
+        &self.tokens[self.pos - 1]
+    }
+
+    /// Check if we've consumed every token
+    fn at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    /// Check if the current token is of the given type
+    fn check(&self, token_type: TokenType) -> bool {
+        self.peek().is_some_and(|tk| tk.token_type == token_type)
+    }
+
+    /// Check if the current token is on the same line as the previous one
+    fn same_line(&self) -> bool {
+        match (
+            self.pos.checked_sub(1).map(|i| &self.tokens[i]),
+            self.peek(),
+        ) {
+            (Some(prev), Some(cur)) => prev.span.end.line == cur.span.start.line,
+            _ => false,
+        }
+    }
+
+    /// Get the position where the current token starts, for opening a node's span
+    fn start(&self) -> Position {
+        match self.peek() {
+            Some(tk) => tk.span.start,
+            None => self.prev_end(),
+        }
+    }
+
+    /// Get the span from the given start up to the end of the last consumed token, for closing a node's span
+    fn finish(&self, start: Position) -> Span {
+        Span::new(start, self.prev_end())
+    }
+
+    /// Get the position where the last consumed token ends
+    fn prev_end(&self) -> Position {
+        match self.pos.checked_sub(1).and_then(|i| self.tokens.get(i)) {
+            Some(tk) => tk.span.end,
+            None => Position::new(1, 1, 0),
+        }
+    }
+
+    /// Consume the current token if it is of the given type, otherwise fail
+    fn expect(&mut self, token_type: TokenType) -> ParseResult<&Token<'a>> {
+        if !self.check(token_type) {
+            return Err(self.error_here(
+                codes::EXPECTED_TOKEN,
+                format!("Expected {}", describe(token_type)),
+            ));
+        }
+
+        Ok(self.advance())
+    }
+
+    // Error recovery
+    // --------------
+
+    /// Report a syntax error, then skip ahead to where parsing can carry on: the start of the next line, a
+    /// closing brace, or one of the given tokens. Brackets opened along the way are skipped along with
+    /// everything in them, so the commas and lines inside them don't stop it
+    fn recover(&mut self, diagnostic: Diagnostic, stops: &[TokenType]) {
+        self.report(diagnostic);
+
+        let mut depth: usize = 0;
+        while let Some(tk) = self.peek() {
+            let token_type: TokenType = tk.token_type;
+            if depth == 0
+                && (token_type == TokenType::RightBrace
+                    || stops.contains(&token_type)
+                    || !self.same_line())
+            {
+                break;
+            }
+
+            match token_type {
+                TokenType::LeftParen | TokenType::LeftBracket | TokenType::LeftBrace => depth += 1,
+                TokenType::RightParen | TokenType::RightBracket | TokenType::RightBrace => {
+                    depth = depth.saturating_sub(1)
+                }
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
+    /// Add a syntax error to the diagnostics, unless one was already reported at the same place or the
+    /// lexer reported an error token at or just before it, since it's most likely a knock-on effect of that
+    fn report(&mut self, diagnostic: Diagnostic) {
+        let offset: usize = diagnostic.span.start.offset;
+        let is_error = |pos: usize| -> bool {
+            self.tokens
+                .get(pos)
+                .is_some_and(|tk| tk.token_type == TokenType::Error)
+        };
+        let after_error: bool = is_error(self.pos) || (self.pos > 0 && is_error(self.pos - 1));
+        if self.last_error != Some(offset) && !after_error {
+            self.last_error = Some(offset);
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    /// Create an error diagnostic pointing at the current token
+    fn error_here(&self, code: &str, msg: String) -> Box<Diagnostic> {
+        let diagnostic: Diagnostic = match self.peek() {
+            Some(tk) => Diagnostic::error(format!("{} but found [{}]", msg, tk.value), tk.span),
+            None => {
+                let end: Position = self.prev_end();
+                Diagnostic::error(
+                    format!("{} but reached end of file", msg),
+                    Span::new(end, end),
+                )
+            }
+        };
+
+        Box::new(diagnostic.with_code(code))
+    }
+}
+
+/// Parse a vector of tokens into a list of top-level items. Error tokens are parsed as error expressions
+/// without another report, since the lexer has already reported them. Every syntax error is added to
+/// the diagnostics, and whatever couldn't be parsed is left in the items as an error node, so the rest
+/// of the program is still there
+pub fn parse(tokens: Vec<Token>, diagnostics: &mut Diagnostics) -> Vec<Item> {
+    let mut parser: Parser = Parser::new(tokens);
+    let items: Vec<Item> = parser.parse_program();
+    diagnostics.append(&mut parser.diagnostics);
+
+    // Log the items if debugging
+    if debugging() {
+        let mut item_strings = String::new();
+        items.iter().for_each(|item| {
+            item_strings.push_str(format!("{} ({})\n    ", item, item.span).as_str())
+        });
+        log_debug(item_strings);
+    }
+
+    items
+}
+
+/// Parse the tokens of a single block, braces included, such as a block being reparsed after an edit.
+/// Nothing inside a block can be documented, so every doc comment in it is reported as unattached.
+/// Returns None if the block doesn't close with its last token, since then it isn't a block on its own
+pub(crate) fn parse_block(tokens: Vec<Token>, diagnostics: &mut Diagnostics) -> Option<Block> {
+    let mut parser: Parser = Parser::new(tokens);
+    let block: Option<Block> = parser.parse_block().ok();
+    while parser.doc_pos < parser.docs.len() {
+        parser.warn_unattached_doc();
+    }
+    diagnostics.append(&mut parser.diagnostics);
+
+    match parser.at_end() && !parser.unclosed {
+        true => block,
+        false => None,
+    }
+}
+
+// Parsing utils
+// -------------
+
+/// Utility function to get the text of a doc comment, without its ;; or the space after it
+fn doc_text(comment: &str) -> String {
+    let text: &str = comment.strip_prefix(";;").unwrap_or(comment);
+    let text: &str = text.strip_prefix(' ').unwrap_or(text);
+    text.trim_end_matches('\r').to_owned()
+}
+
+/// Utility function to map a token to its binary operator, binding power and associativity
+pub(crate) fn binary_op(token_type: &TokenType) -> Option<(BinaryOp, u8, bool)> {
+    let op = match token_type {
+        TokenType::DotDotQuestion => (BinaryOp::Coalesce, BP_COALESCE, true),
+        TokenType::PipePipe => (BinaryOp::Or, BP_OR, false),
+        TokenType::AndAnd => (BinaryOp::And, BP_AND, false),
+        TokenType::EqualsEquals => (BinaryOp::Equals, BP_COMPARISON, false),
+        TokenType::BangEquals => (BinaryOp::NotEquals, BP_COMPARISON, false),
+        TokenType::TildeEquals => (BinaryOp::Matches, BP_COMPARISON, false),
+        TokenType::LeftAngle => (BinaryOp::Less, BP_COMPARISON, false),
+        TokenType::LessOrEquals => (BinaryOp::LessOrEquals, BP_COMPARISON, false),
+        TokenType::RightAngle => (BinaryOp::Greater, BP_COMPARISON, false),
+        TokenType::GreaterOrEquals => (BinaryOp::GreaterOrEquals, BP_COMPARISON, false),
+        TokenType::Pipe => (BinaryOp::BitOr, BP_BIT_OR, false),
+        TokenType::Caret => (BinaryOp::BitXor, BP_BIT_XOR, false),
+        TokenType::And => (BinaryOp::BitAnd, BP_BIT_AND, false),
+        TokenType::LeftShift => (BinaryOp::LeftShift, BP_SHIFT, false),
+        TokenType::RightShift => (BinaryOp::RightShift, BP_SHIFT, false),
+        TokenType::Plus => (BinaryOp::Add, BP_SUM, false),
+        TokenType::Minus => (BinaryOp::Subtract, BP_SUM, false),
+        TokenType::Star => (BinaryOp::Multiply, BP_PRODUCT, false),
+        TokenType::Slash => (BinaryOp::Divide, BP_PRODUCT, false),
+        TokenType::Percent => (BinaryOp::Remainder, BP_PRODUCT, false),
+        _ => return None,
+    };
+
+    Some(op)
+}
+
+/// Utility function to describe a token type the way the source code would spell it, for diagnostics
+fn describe(token_type: TokenType) -> String {
+    match OPERATORS
+        .iter()
+        .find(|operator| operator.token_type == token_type)
+    {
+        Some(operator) => format!("[{}]", operator.symbol),
+        None if token_type == TokenType::Identifier => "a name".to_owned(),
+        None => token_type.to_string(),
+    }
+}
+
+/// Utility function to map a token to its assignment operator
+fn assign_op(token_type: &TokenType) -> Option<AssignOp> {
+    match token_type {
+        TokenType::Equals => Some(AssignOp::Assign),
+        TokenType::LeftArrow => Some(AssignOp::Send),
+        TokenType::PlusEquals => Some(AssignOp::AddAssign),
+        TokenType::MinusEquals => Some(AssignOp::SubtractAssign),
+        TokenType::StarEquals => Some(AssignOp::MultiplyAssign),
+        TokenType::SlashEquals => Some(AssignOp::DivideAssign),
+        TokenType::PercentEquals => Some(AssignOp::RemainderAssign),
+        TokenType::AndEquals => Some(AssignOp::BitAndAssign),
+        TokenType::CaretEquals => Some(AssignOp::BitXorAssign),
+        TokenType::LeftShiftEquals => Some(AssignOp::LeftShiftAssign),
+        TokenType::RightShiftEquals => Some(AssignOp::RightShiftAssign),
+        _ => None,
+    }
+}
+
+/// Utility function to parse the pieces of a form string. Each embedded expression is parsed on its own,
+/// from the tokens between its braces, and its syntax errors are added to the diagnostics
+fn parse_interpolation(
+    segments: &[FormSegment],
+    diagnostics: &mut Diagnostics,
+) -> Vec<InterpolationPart> {
+    let mut parts: Vec<InterpolationPart> = Vec::new();
+    for segment in segments {
+        match segment {
+            FormSegment::Text(text, span) => {
+                parts.push(InterpolationPart::Text(text.to_string(), *span))
+            }
+            FormSegment::Code(tokens, _) => {
+                let mut parser: Parser = Parser::new(tokens.clone());
+                if let Err(diagnostic) = parser.expect(TokenType::LeftBrace) {
+                    parser.report(*diagnostic);
+                }
+                let expr: Expr = parser.parse_expression_or_error(&[]);
+                if let Err(diagnostic) = parser.expect(TokenType::RightBrace) {
+                    parser.report(*diagnostic);
+                }
+                diagnostics.append(&mut parser.diagnostics);
+                parts.push(InterpolationPart::Expr(expr));
+            }
+        }
+    }
+
+    parts
+}
+
+/// Utility function to map a literal token to its value
+fn literal(tk: &Token) -> Option<Literal> {
+    match (tk.token_type, &tk.literal) {
+        (TokenType::Int, Some(LiteralValue::Int(value, suffix))) => {
+            Some(Literal::Int(*value, *suffix))
+        }
+        (TokenType::Float, Some(LiteralValue::Float(value, suffix))) => {
+            Some(Literal::Float(*value, *suffix))
+        }
+        (TokenType::String, Some(LiteralValue::Str(value))) => {
+            Some(Literal::String(value.to_string()))
+        }
+        (TokenType::Char, Some(LiteralValue::Char(value))) => Some(Literal::Char(*value)),
+        _ => None,
+    }
+}
+
+/// Utility function to tell us if an expression is a place that can be assigned to
+fn is_place(expr: &Expr) -> bool {
+    matches!(
+        expr.kind,
+        ExprKind::Identifier(_)
+            | ExprKind::Index { .. }
+            | ExprKind::Field { .. }
+            | ExprKind::Path { .. }
+    )
+}
@@ -0,0 +1,578 @@
+use unicode_xid::UnicodeXID;
+
+use crate::{
+    ast::{
+        AssignOp, Ast, BinaryOp, Binding, Block, Expr, ExprKind, Function, Ident,
+        InterpolationPart, Item, ItemKind, MatchArm, Param, Pattern, PatternKind, Stmt, StmtKind,
+        Type, TypeKind, UnaryOp,
+    },
+    dialect::Dialect,
+    operators::{OPERATORS, Operators, STANDARD},
+    parser::{BP_MATCH, BP_PREFIX, binary_op},
+    token::TokenType,
+};
+
+// Binding powers of the expressions that aren't operators, on the same scale as the parser's
+const BP_OPEN: u8 = 0; // loops and function literals, which run on to the end of their body
+const BP_POSTFIX: u8 = BP_PREFIX + 1; // names, literals, groupings, calls, indexes, fields and paths
+
+// Spaces per level of indentation
+const INDENT: &str = "    ";
+
+/// Print a parsed program back out as source code. With a dialect, every operator is spelled the way
+/// the dialect writes it, and without one the program is printed as canonical Rumil. Printing fails if
+/// the program uses a name that the dialect reads as a keyword, since it couldn't be read back, or if it
+/// has syntax errors
+pub fn print_program(ast: &Ast, dialect: Option<&Dialect>) -> Result<String, String> {
+    let mut printer: Printer = Printer::new(dialect);
+    for (i, item) in ast.items.iter().enumerate() {
+        // Functions get a blank line around them
+        let is_function: bool = matches!(item.kind, ItemKind::Function { .. });
+        let after_function: bool =
+            i > 0 && matches!(ast.items[i - 1].kind, ItemKind::Function { .. });
+        if i > 0 && (is_function || after_function) {
+            printer.out.push('\n');
+        }
+        printer.item(item);
+        printer.out.push('\n');
+    }
+
+    match printer.problem {
+        Some(msg) => Err(msg),
+        None => Ok(printer.out),
+    }
+}
+
+/// Prints AST nodes as source code, keeping track of what was written last so tokens never run together
+struct Printer<'d> {
+    out: String,                  // the source code printed so far
+    indent: usize,                // how many blocks deep the printer is
+    dialect: Option<&'d Dialect>, // how to spell operators, if not as canonical Rumil
+    operators: &'d Operators,     // the operators the printed code will be scanned with
+    last_op: Option<usize>,       // where the operator that was just printed starts, if one was
+    problem: Option<String>,      // the first thing found that can't be printed
+}
+
+impl<'d> Printer<'d> {
+    /// Create a new Printer
+    fn new(dialect: Option<&'d Dialect>) -> Printer<'d> {
+        Printer {
+            out: String::new(),
+            indent: 0,
+            dialect,
+            operators: dialect.map_or(&STANDARD, |dialect| dialect.operators()),
+            last_op: None,
+            problem: None,
+        }
+    }
+
+    // Writing
+    // -------
+
+    /// Write some text, with a space before it if it would otherwise run into what was written last
+    fn write(&mut self, text: &str) {
+        let Some(next) = text.chars().next() else {
+            return;
+        };
+
+        // Two words written together would scan as one identifier, and an operator followed by the
+        // start of another can scan as a longer operator
+        let runs_together: bool = match (self.out.chars().next_back(), self.last_op) {
+            (Some(prev), _) if prev.is_xid_continue() && next.is_xid_continue() => true,
+            (_, Some(start)) => {
+                let op_len: usize = self.out.len() - start;
+                let joined: String = format!("{}{}", &self.out[start..], text);
+                self.operators
+                    .longest_match(&joined)
+                    .is_some_and(|(_, len)| len > op_len)
+            }
+            _ => false,
+        };
+        if runs_together {
+            self.out.push(' ');
+        }
+
+        self.out.push_str(text);
+        self.last_op = None;
+    }
+
+    /// Write an operator, spelled the way the dialect writes it
+    fn op(&mut self, token_type: TokenType) {
+        let spelling: &str = self
+            .dialect
+            .and_then(|dialect| dialect.spelling(token_type))
+            .or_else(|| {
+                OPERATORS
+                    .iter()
+                    .find(|operator| operator.token_type == token_type)
+                    .map(|operator| operator.symbol)
+            })
+            .unwrap_or_default();
+        self.write(spelling);
+        self.last_op = Some(self.out.len() - spelling.len());
+    }
+
+    /// Write a name, noting it if the dialect would read it as a keyword instead
+    fn name(&mut self, name: &str) {
+        if let Some(dialect) = self.dialect
+            && self.problem.is_none()
+            && dialect.keyword(name).is_some()
+        {
+            self.problem = Some(format!(
+                "[{}] is a keyword in the {} dialect, so it can't be used as a name",
+                name, dialect.name
+            ));
+        }
+        self.write(name);
+    }
+
+    /// Write a space
+    fn space(&mut self) {
+        self.out.push(' ');
+        self.last_op = None;
+    }
+
+    /// Start a new line at the current indentation
+    fn newline(&mut self) {
+        self.out.push('\n');
+        self.out.push_str(&INDENT.repeat(self.indent));
+        self.last_op = None;
+    }
+
+    /// Write a comma separated list of nodes between two operators. A tuple with a single node gets a
+    /// trailing comma, or it would be read back as just a grouping
+    fn list<T>(
+        &mut self,
+        open: TokenType,
+        close: TokenType,
+        nodes: &[T],
+        is_tuple: bool,
+        mut print_node: impl FnMut(&mut Self, &T),
+    ) {
+        self.op(open);
+        for (i, node) in nodes.iter().enumerate() {
+            if i > 0 {
+                self.op(TokenType::Comma);
+                self.space();
+            }
+            print_node(self, node);
+        }
+        if is_tuple && nodes.len() == 1 {
+            self.op(TokenType::Comma);
+        }
+        self.op(close);
+    }
+
+    // Items and statements
+    // --------------------
+
+    /// Print a top-level item, along with its doc comments
+    fn item(&mut self, item: &Item) {
+        for doc in &item.docs {
+            self.out.push_str(";;");
+            if !doc.is_empty() {
+                self.out.push(' ');
+                self.out.push_str(doc);
+            }
+            self.newline();
+        }
+
+        match &item.kind {
+            ItemKind::Function { name, function } => {
+                self.ident(name);
+                self.space();
+                self.op(TokenType::ColonEquals);
+                self.space();
+                self.function(function);
+            }
+            ItemKind::Binding(binding) => self.binding(binding),
+            ItemKind::Stmt(stmt) => self.stmt(stmt),
+        }
+    }
+
+    /// Print a function literal
+    fn function(&mut self, function: &Function) {
+        self.list(
+            TokenType::LeftParen,
+            TokenType::RightParen,
+            &function.params,
+            false,
+            Self::param,
+        );
+        self.space();
+        self.op(TokenType::RightArrow);
+        if let Some(ty) = &function.return_type {
+            self.space();
+            self.ty(ty);
+        }
+        self.space();
+        self.block(&function.body);
+    }
+
+    /// Print a function parameter
+    fn param(&mut self, param: &Param) {
+        self.ident(&param.name);
+        if let Some(ty) = &param.ty {
+            self.op(TokenType::Colon);
+            self.space();
+            self.ty(ty);
+        }
+    }
+
+    /// Print a name along with where it was written
+    fn ident(&mut self, ident: &Ident) {
+        self.name(&ident.name);
+    }
+
+    /// Print a block, with each statement on its own line
+    fn block(&mut self, block: &Block) {
+        self.op(TokenType::LeftBrace);
+        if !block.statements.is_empty() {
+            self.indent += 1;
+            for stmt in &block.statements {
+                self.newline();
+                self.stmt(stmt);
+            }
+            self.indent -= 1;
+            self.newline();
+        }
+        self.op(TokenType::RightBrace);
+    }
+
+    /// Print a statement
+    fn stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Binding(binding) => self.binding(binding),
+            StmtKind::Assign { target, op, value } => {
+                self.expr(target, BP_OPEN);
+                self.space();
+                self.op(assign_token(*op));
+                self.space();
+                self.expr(value, BP_OPEN);
+            }
+            StmtKind::Return(value) => {
+                self.op(TokenType::RightArrow);
+                if let Some(value) = value {
+                    self.space();
+                    self.expr(value, BP_OPEN);
+                }
+            }
+
+            StmtKind::Expr(expr) => self.expr(expr, BP_OPEN),
+        }
+    }
+
+    /// Print a binding
+    fn binding(&mut self, binding: &Binding) {
+        self.pattern(&binding.pattern);
+        if let Some(ty) = &binding.ty {
+            self.op(TokenType::Colon);
+            self.space();
+            self.ty(ty);
+        }
+        self.space();
+        self.op(TokenType::ColonEquals);
+        self.space();
+        self.expr(&binding.value, BP_OPEN);
+    }
+
+    // Expressions
+    // -----------
+
+    /// Print an expression, in parentheses if it binds looser than min_bp
+    fn expr(&mut self, expr: &Expr, min_bp: u8) {
+        if binding_power(expr) < min_bp {
+            self.op(TokenType::LeftParen);
+            self.expr(expr, BP_OPEN);
+            self.op(TokenType::RightParen);
+            return;
+        }
+
+        match &expr.kind {
+            ExprKind::Identifier(name) => self.name(name),
+            ExprKind::Print => self.op(TokenType::Dollar),
+            ExprKind::Literal(literal) => self.write(&literal.to_string()),
+            ExprKind::Array(items) => self.list(
+                TokenType::LeftBracket,
+                TokenType::RightBracket,
+                items,
+                false,
+                |p, item| p.expr(item, BP_OPEN),
+            ),
+            ExprKind::Tuple(items) => {
+                self.list(
+                    TokenType::LeftParen,
+                    TokenType::RightParen,
+                    items,
+                    true,
+                    |p, item| p.expr(item, BP_OPEN),
+                );
+            }
+            ExprKind::Block(block) => self.block(block),
+            ExprKind::Function(function) => self.function(function),
+
+            ExprKind::Unary { op, operand } => {
+                self.op(unary_token(*op));
+                self.expr(operand, BP_PREFIX);
+            }
+            ExprKind::Binary { op, left, right } => {
+                let token_type: TokenType = binary_token(*op);
+                let (bp, right_assoc) = binary_op(&token_type)
+                    .map_or((BP_POSTFIX, false), |(_, bp, right_assoc)| {
+                        (bp, right_assoc)
+                    });
+                self.expr(left, if right_assoc { bp + 1 } else { bp });
+                self.space();
+                self.op(token_type);
+                self.space();
+                self.expr(right, if right_assoc { bp } else { bp + 1 });
+            }
+
+            ExprKind::Call { callee, args } => {
+                self.expr(callee, BP_POSTFIX);
+                self.list(
+                    TokenType::LeftParen,
+                    TokenType::RightParen,
+                    args,
+                    false,
+                    |p, arg| p.expr(arg, BP_OPEN),
+                );
+            }
+            ExprKind::Index { target, index } => {
+                self.expr(target, BP_POSTFIX);
+                self.op(TokenType::LeftBracket);
+                self.expr(index, BP_OPEN);
+                self.op(TokenType::RightBracket);
+            }
+            ExprKind::Field {
+                target,
+                name,
+                optional,
+            } => {
+                self.expr(target, BP_POSTFIX);
+                self.op(if *optional {
+                    TokenType::DotQuestion
+                } else {
+                    TokenType::Dot
+                });
+                self.ident(name);
+            }
+            ExprKind::Path { target, name } => {
+                self.expr(target, BP_POSTFIX);
+                self.op(TokenType::ColonColon);
+                self.ident(name);
+            }
+
+            ExprKind::Match { subject, arms } => {
+                self.expr(subject, BP_MATCH);
+                self.space();
+                self.op(TokenType::Question);
+                self.space();
+                self.op(TokenType::LeftBrace);
+                if !arms.is_empty() {
+                    self.indent += 1;
+                    for arm in arms {
+                        self.newline();
+                        self.arm(arm);
+                    }
+                    self.indent -= 1;
+                    self.newline();
+                }
+                self.op(TokenType::RightBrace);
+            }
+            ExprKind::Loop { condition, body } => {
+                self.op(TokenType::At);
+                if let Some(condition) = condition {
+                    self.space();
+
+                    // A block right after @ would be taken as the loop's body
+                    match condition.kind {
+                        ExprKind::Block(_) => self.expr(condition, BP_POSTFIX + 1),
+                        _ => self.expr(condition, BP_OPEN),
+                    }
+                }
+                self.space();
+                self.block(body);
+            }
+
+            // The braces around embedded expressions belong to the form string, so they're never respelled
+            ExprKind::Interpolation(parts) => {
+                self.write("`");
+                for part in parts {
+                    match part {
+                        InterpolationPart::Text(..) => self.out.push_str(&part.to_string()),
+                        InterpolationPart::Expr(expr) => {
+                            self.out.push('{');
+                            self.last_op = None;
+                            self.expr(expr, BP_OPEN);
+                            self.out.push('}');
+                        }
+                    }
+                }
+                self.out.push('`');
+                self.last_op = None;
+            }
+            ExprKind::Error => {
+                if self.problem.is_none() {
+                    self.problem = Some(format!(
+                        "Can't print a program with a syntax error at {}",
+                        expr.span
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Print a match arm
+    fn arm(&mut self, arm: &MatchArm) {
+        self.pattern(&arm.pattern);
+        self.space();
+        self.op(TokenType::EqualsArrow);
+        self.space();
+        self.expr(&arm.value, BP_OPEN);
+    }
+
+    // Patterns and types
+    // ------------------
+
+    /// Print a pattern
+    fn pattern(&mut self, pattern: &Pattern) {
+        match &pattern.kind {
+            PatternKind::Wildcard => self.op(TokenType::Underscore),
+            PatternKind::Identifier(name) => self.name(name),
+            PatternKind::Literal { literal, negative } => {
+                if *negative {
+                    self.op(TokenType::Minus);
+                }
+                self.write(&literal.to_string());
+            }
+            PatternKind::Tuple(items) => {
+                self.list(
+                    TokenType::LeftParen,
+                    TokenType::RightParen,
+                    items,
+                    true,
+                    Self::pattern,
+                );
+            }
+            PatternKind::Array(items) => self.list(
+                TokenType::LeftBracket,
+                TokenType::RightBracket,
+                items,
+                false,
+                Self::pattern,
+            ),
+        }
+    }
+
+    /// Print a type annotation
+    fn ty(&mut self, ty: &Type) {
+        match &ty.kind {
+            TypeKind::Named(path) => {
+                for (i, ident) in path.iter().enumerate() {
+                    if i > 0 {
+                        self.op(TokenType::ColonColon);
+                    }
+                    self.ident(ident);
+                }
+            }
+            TypeKind::Array(item) => {
+                self.op(TokenType::LeftBracket);
+                self.ty(item);
+                self.op(TokenType::RightBracket);
+            }
+            TypeKind::Tuple(items) => {
+                self.list(
+                    TokenType::LeftParen,
+                    TokenType::RightParen,
+                    items,
+                    true,
+                    Self::ty,
+                );
+            }
+            TypeKind::Function { params, ret } => {
+                self.list(
+                    TokenType::LeftParen,
+                    TokenType::RightParen,
+                    params,
+                    false,
+                    Self::ty,
+                );
+                self.space();
+                self.op(TokenType::RightArrow);
+                self.space();
+                self.ty(ret);
+            }
+            TypeKind::Optional(item) => {
+                self.op(TokenType::Question);
+                self.ty(item);
+            }
+        }
+    }
+}
+
+/// Utility function to get how tightly an expression binds, to tell if it needs parentheses
+fn binding_power(expr: &Expr) -> u8 {
+    match &expr.kind {
+        ExprKind::Loop { .. } | ExprKind::Function(_) => BP_OPEN,
+        ExprKind::Match { .. } => BP_MATCH,
+        ExprKind::Binary { op, .. } => {
+            binary_op(&binary_token(*op)).map_or(BP_POSTFIX, |(_, bp, _)| bp)
+        }
+        ExprKind::Unary { .. } => BP_PREFIX,
+        _ => BP_POSTFIX,
+    }
+}
+
+/// Utility function to map a unary operator to its token
+fn unary_token(op: UnaryOp) -> TokenType {
+    match op {
+        UnaryOp::Not => TokenType::Bang,
+        UnaryOp::Negate => TokenType::Minus,
+        UnaryOp::BitNot => TokenType::Tilde,
+        UnaryOp::Length => TokenType::Hash,
+    }
+}
+
+/// Utility function to map a binary operator to its token
+fn binary_token(op: BinaryOp) -> TokenType {
+    match op {
+        BinaryOp::Coalesce => TokenType::DotDotQuestion,
+        BinaryOp::Or => TokenType::PipePipe,
+        BinaryOp::And => TokenType::AndAnd,
+        BinaryOp::Equals => TokenType::EqualsEquals,
+        BinaryOp::NotEquals => TokenType::BangEquals,
+        BinaryOp::Matches => TokenType::TildeEquals,
+        BinaryOp::Less => TokenType::LeftAngle,
+        BinaryOp::LessOrEquals => TokenType::LessOrEquals,
+        BinaryOp::Greater => TokenType::RightAngle,
+        BinaryOp::GreaterOrEquals => TokenType::GreaterOrEquals,
+        BinaryOp::BitOr => TokenType::Pipe,
+        BinaryOp::BitXor => TokenType::Caret,
+        BinaryOp::BitAnd => TokenType::And,
+        BinaryOp::LeftShift => TokenType::LeftShift,
+        BinaryOp::RightShift => TokenType::RightShift,
+        BinaryOp::Add => TokenType::Plus,
+        BinaryOp::Subtract => TokenType::Minus,
+        BinaryOp::Multiply => TokenType::Star,
+        BinaryOp::Divide => TokenType::Slash,
+        BinaryOp::Remainder => TokenType::Percent,
+    }
+}
+
+/// Utility function to map an assignment operator to its token
+fn assign_token(op: AssignOp) -> TokenType {
+    match op {
+        AssignOp::Assign => TokenType::Equals,
+        AssignOp::Send => TokenType::LeftArrow,
+        AssignOp::AddAssign => TokenType::PlusEquals,
+        AssignOp::SubtractAssign => TokenType::MinusEquals,
+        AssignOp::MultiplyAssign => TokenType::StarEquals,
+        AssignOp::DivideAssign => TokenType::SlashEquals,
+        AssignOp::RemainderAssign => TokenType::PercentEquals,
+        AssignOp::BitAndAssign => TokenType::AndEquals,
+        AssignOp::BitXorAssign => TokenType::CaretEquals,
+        AssignOp::LeftShiftAssign => TokenType::LeftShiftEquals,
+        AssignOp::RightShiftAssign => TokenType::RightShiftEquals,
+    }
+}
@@ -0,0 +1,72 @@
+use std::{fmt, ops::Range};
+
+use serde::{Deserialize, Serialize};
+
+/// A position in the source code. Lines and columns both start at 1, and columns count chars.
+/// The byte offset starts at 0 and can be used to slice the source code directly
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub struct Position {
+    pub line: i32,
+    pub col: i32,
+    pub offset: usize,
+}
+
+impl Position {
+    /// Create a new Position
+    pub fn new(line: i32, col: i32, offset: usize) -> Position {
+        Position { line, col, offset }
+    }
+
+    /// Get the position just past some text that starts at this one, counting lines and columns the way
+    /// the lexer does
+    pub fn after(mut self, text: &str) -> Position {
+        for c in text.chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.col = 1;
+            } else {
+                self.col += 1;
+            }
+            self.offset += c.len_utf8();
+        }
+        self
+    }
+}
+
+/// A range of source code. The end position is exclusive: it points just past the last char
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    /// Create a new Span
+    pub fn new(start: Position, end: Position) -> Span {
+        Span { start, end }
+    }
+
+    /// Get the byte range covered by this Span, for slicing the source code
+    pub fn range(&self) -> Range<usize> {
+        self.start.offset..self.end.offset
+    }
+
+    /// Create a Span that covers both this one and another one
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end,
+        }
+    }
+}
+
+/// Allow pretty-printing of Spans
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}-{}:{}",
+            self.start.line, self.start.col, self.end.line, self.end.col
+        )
+    }
+}
@@ -0,0 +1,1257 @@
+use std::{fmt, ops::Range, rc::Rc, sync::Arc};
+
+use crate::{
+    ast::{
+        Ast, Binding, Block, Expr, ExprKind, Function, Ident, InterpolationPart, Item, ItemKind,
+        Pattern, PatternKind, Stmt, StmtKind, Type, TypeKind,
+    },
+    diagnostic::{Diagnostic, Diagnostics},
+    lexer::Lexer,
+    parser,
+    span::{Position, Span},
+    token::{Token, TokenType, Trivia, TriviaKind},
+};
+
+/// What a node of the syntax tree is. Each kind matches a node of the typed Ast, except expression
+/// statements and names, which are just their expression and token
+#[derive(strum_macros::Display, Clone, Copy, PartialEq, Debug)]
+pub enum SyntaxKind {
+    Program,
+
+    // Items and statements
+    NamedFunction, // name := (params) -> ReturnType { body }
+    Binding,
+    Assign,
+    Return,
+    Block,
+
+    // Functions
+    Function,
+    Param,
+
+    // Expressions
+    Name,
+    Print,
+    Literal,
+    Array,
+    Tuple,
+    Unary,
+    Binary,
+    Call,
+    Index,
+    Field,
+    Path,
+    Match,
+    MatchArm,
+    Loop,
+    Interpolation,
+    Error, // source code with a syntax error in it
+
+    // Patterns
+    WildcardPattern,
+    NamePattern,
+    LiteralPattern,
+    TuplePattern,
+    ArrayPattern,
+
+    // Types
+    NamedType,
+    ArrayType,
+    TupleType,
+    FunctionType,
+    OptionalType,
+}
+
+/// What a token of the syntax tree is: one the parser reads, or trivia between them
+#[derive(Clone, Copy, PartialEq)]
+pub enum TokenKind {
+    Token(TokenType),
+    Trivia(TriviaKind),
+}
+
+// Green tree
+// ----------
+
+/// An immutable node of the syntax tree. Green nodes only know their own text and children, not
+/// where they are, so an unchanged subtree can be shared between versions of a tree
+pub struct GreenNode {
+    kind: SyntaxKind,
+    width: usize,                // length of the node's text in bytes
+    children: Vec<GreenElement>, // nodes and tokens, in source order
+}
+
+/// An immutable token of the syntax tree, along with its exact source text
+pub struct GreenToken {
+    kind: TokenKind,
+    text: String,
+}
+
+/// A child of a green node
+#[derive(Clone)]
+pub enum GreenElement {
+    Node(Arc<GreenNode>),
+    Token(Arc<GreenToken>),
+}
+
+impl GreenNode {
+    /// Create a new GreenNode from its children
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> GreenNode {
+        let width: usize = children.iter().map(GreenElement::width).sum();
+        GreenNode {
+            kind,
+            width,
+            children,
+        }
+    }
+
+    /// Get what this node is
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    /// Get the length of this node's text in bytes
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Get this node's children, in source order
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+
+    /// Create a copy of this node with one of its children swapped out. The other children are shared
+    fn with_child(&self, index: usize, child: GreenElement) -> GreenNode {
+        let mut children: Vec<GreenElement> = self.children.clone();
+        children[index] = child;
+        GreenNode::new(self.kind, children)
+    }
+}
+
+impl GreenToken {
+    /// Create a new GreenToken
+    pub fn new(kind: TokenKind, text: String) -> GreenToken {
+        GreenToken { kind, text }
+    }
+
+    /// Get what this token is
+    pub fn kind(&self) -> TokenKind {
+        self.kind
+    }
+
+    /// Get this token's source text
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl GreenElement {
+    /// Get the length of this element's text in bytes
+    pub fn width(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.width,
+            GreenElement::Token(token) => token.text.len(),
+        }
+    }
+}
+
+// Red tree
+// --------
+
+/// A node of the syntax tree, seen from the root: unlike a green node, it knows its parent and where it
+/// is in the source code. Red nodes are created as the tree is walked, and are cheap to clone
+#[derive(Clone)]
+pub struct SyntaxNode {
+    data: Rc<NodeData>,
+}
+
+struct NodeData {
+    green: Arc<GreenNode>,
+    parent: Option<SyntaxNode>, // the node this one is a child of, unless it's the root
+    index: usize,               // which child of its parent it is
+    offset: usize,              // where it starts in the source code, in bytes
+}
+
+/// A token of the syntax tree, seen from the root
+#[derive(Clone)]
+pub struct SyntaxToken {
+    green: Arc<GreenToken>,
+    parent: SyntaxNode,
+    index: usize,  // which child of its parent it is
+    offset: usize, // where it starts in the source code, in bytes
+}
+
+/// A child of a syntax node
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxNode {
+    /// Create the root of a syntax tree from its green node
+    pub fn new_root(green: Arc<GreenNode>) -> SyntaxNode {
+        SyntaxNode {
+            data: Rc::new(NodeData {
+                green,
+                parent: None,
+                index: 0,
+                offset: 0,
+            }),
+        }
+    }
+
+    /// Get what this node is
+    pub fn kind(&self) -> SyntaxKind {
+        self.data.green.kind
+    }
+
+    /// Get the green node behind this node
+    pub fn green(&self) -> &Arc<GreenNode> {
+        &self.data.green
+    }
+
+    /// Get the byte range of the source code this node covers
+    pub fn range(&self) -> Range<usize> {
+        self.data.offset..self.data.offset + self.data.green.width
+    }
+
+    /// Get this node's source text, exactly as written
+    pub fn text(&self) -> String {
+        self.to_string()
+    }
+
+    /// Get the node this one is a child of, unless it's the root
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        self.data.parent.clone()
+    }
+
+    /// Get this node's children, both nodes and tokens, in source order
+    pub fn children_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> + '_ {
+        let mut offset: usize = self.data.offset;
+        self.data
+            .green
+            .children
+            .iter()
+            .enumerate()
+            .map(move |(index, child)| {
+                let start: usize = offset;
+                offset += child.width();
+                match child {
+                    GreenElement::Node(green) => SyntaxElement::Node(SyntaxNode {
+                        data: Rc::new(NodeData {
+                            green: green.clone(),
+                            parent: Some(self.clone()),
+                            index,
+                            offset: start,
+                        }),
+                    }),
+                    GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                        green: green.clone(),
+                        parent: self.clone(),
+                        index,
+                        offset: start,
+                    }),
+                }
+            })
+    }
+
+    /// Get this node's child nodes, in source order
+    pub fn children(&self) -> impl Iterator<Item = SyntaxNode> + '_ {
+        self.children_with_tokens().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    /// Get every token under this node, trivia included, in source order
+    pub fn tokens(&self) -> Vec<SyntaxToken> {
+        let mut tokens: Vec<SyntaxToken> = Vec::new();
+        for child in self.children_with_tokens() {
+            match child {
+                SyntaxElement::Node(node) => tokens.append(&mut node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+        tokens
+    }
+
+    /// Find the smallest node under this one, or this one itself, that covers the given byte range
+    pub fn covering_node(&self, range: Range<usize>) -> SyntaxNode {
+        let mut node: SyntaxNode = self.clone();
+        loop {
+            let covering: Option<SyntaxNode> = node.children().find(|child| {
+                let child_range: Range<usize> = child.range();
+                child_range.start <= range.start && range.end <= child_range.end
+            });
+            match covering {
+                Some(child) => node = child,
+                None => return node,
+            }
+        }
+    }
+
+    /// Find the token that the given byte offset falls in, if any
+    pub fn token_at(&self, offset: usize) -> Option<SyntaxToken> {
+        self.tokens()
+            .into_iter()
+            .find(|token| token.range().contains(&offset))
+    }
+
+    /// Swap this node out for a new one, and get the root of the resulting tree. The original tree
+    /// isn't changed, and every node outside the path up to the root is shared with it
+    pub fn replace_with(&self, green: GreenNode) -> SyntaxNode {
+        self.replace_in_parents(Arc::new(green))
+    }
+
+    /// Describe the tree under this node, one element per line, with the kind and byte range of each node
+    /// and the kind and text of each token
+    pub fn dump(&self) -> String {
+        let mut out: String = String::new();
+        self.dump_into(&mut out, 0);
+        out
+    }
+
+    /// Utility function to rebuild the path from this node up to the root around a new version of it
+    fn replace_in_parents(&self, mut green: Arc<GreenNode>) -> SyntaxNode {
+        let mut node: SyntaxNode = self.clone();
+        while let Some(parent) = node.parent() {
+            green = Arc::new(
+                parent
+                    .data
+                    .green
+                    .with_child(node.data.index, GreenElement::Node(green)),
+            );
+            node = parent;
+        }
+        SyntaxNode::new_root(green)
+    }
+
+    /// Utility function to describe this node and everything under it at some depth
+    fn dump_into(&self, out: &mut String, depth: usize) {
+        let range: Range<usize> = self.range();
+        out.push_str(&format!(
+            "{}{}@{}..{}\n",
+            "  ".repeat(depth),
+            self.kind(),
+            range.start,
+            range.end
+        ));
+        for child in self.children_with_tokens() {
+            match child {
+                SyntaxElement::Node(node) => node.dump_into(out, depth + 1),
+                SyntaxElement::Token(token) => out.push_str(&format!(
+                    "{}{} {:?}\n",
+                    "  ".repeat(depth + 1),
+                    token.kind(),
+                    token.text()
+                )),
+            }
+        }
+    }
+}
+
+impl SyntaxToken {
+    /// Get what this token is
+    pub fn kind(&self) -> TokenKind {
+        self.green.kind
+    }
+
+    /// Get this token's source text, exactly as written
+    pub fn text(&self) -> &str {
+        &self.green.text
+    }
+
+    /// Get the byte range of the source code this token covers
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.green.text.len()
+    }
+
+    /// Get the node this token is a child of
+    pub fn parent(&self) -> SyntaxNode {
+        self.parent.clone()
+    }
+
+    /// Tell us if this token is trivia, which the parser doesn't read
+    pub fn is_trivia(&self) -> bool {
+        matches!(self.green.kind, TokenKind::Trivia(_))
+    }
+
+    /// Swap this token out for a new one, and get the root of the resulting tree. The original tree
+    /// isn't changed, and every node outside the path up to the root is shared with it
+    pub fn replace_with(&self, green: GreenToken) -> SyntaxNode {
+        let parent: GreenNode = self
+            .parent
+            .data
+            .green
+            .with_child(self.index, GreenElement::Token(Arc::new(green)));
+        self.parent.replace_in_parents(Arc::new(parent))
+    }
+}
+
+/// Allow printing a green node as its source text
+impl fmt::Display for GreenNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for child in &self.children {
+            match child {
+                GreenElement::Node(node) => write!(f, "{}", node)?,
+                GreenElement::Token(token) => write!(f, "{}", token.text)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Allow printing a syntax node as its source text
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.data.green)
+    }
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenKind::Token(token_type) => write!(f, "{}", token_type),
+            TokenKind::Trivia(kind) => write!(f, "{}", kind),
+        }
+    }
+}
+
+// Syntax trees
+// ------------
+
+/// A source file parsed into a lossless syntax tree, with the typed Ast as a view on top of it. The
+/// syntax tree holds every char of the source code, comments and formatting included, while each Ast
+/// node covers the span of the syntax node it was parsed from
+pub struct SyntaxTree {
+    green: Arc<GreenNode>,
+    ast: Box<Ast>,
+    scanned: Diagnostics,         // problems found while scanning
+    parsed: Diagnostics,          // problems found while parsing
+    read_past: Vec<Range<usize>>, // tokens whose scan read past their end, up to the furthest byte read
+}
+
+/// A change to source code: a byte range of it replaced with new text
+pub struct TextEdit {
+    pub range: Range<usize>, // the bytes to replace, which are empty to insert text
+    pub text: String,        // what to replace them with, which is empty to delete them
+}
+
+impl TextEdit {
+    /// Create a new TextEdit
+    pub fn new(range: Range<usize>, text: &str) -> TextEdit {
+        TextEdit {
+            range,
+            text: text.to_owned(),
+        }
+    }
+}
+
+impl SyntaxTree {
+    /// Scan and parse source code into a syntax tree and its Ast. Syntax errors don't stop parsing:
+    /// they're kept in the diagnostics, and the source code around them is still in the tree
+    pub fn parse(source_code: &str) -> SyntaxTree {
+        let mut lexer: Lexer = Lexer::lossless(source_code);
+        let tokens: Vec<Token> = lexer.by_ref().collect();
+        let scanned: Diagnostics = lexer.take_diagnostics();
+        let mut parsed: Diagnostics = Diagnostics::new();
+        let ast: Box<Ast> = Ast::new(tokens.clone(), &mut parsed);
+        let green: Arc<GreenNode> = Arc::new(build(&tokens, &ast));
+        SyntaxTree {
+            green,
+            ast,
+            scanned,
+            parsed,
+            read_past: lexer.take_read_past(),
+        }
+    }
+
+    /// Apply an edit to the source code, and update the tree to match. Only the tokens the edit touches
+    /// are scanned again, and only the smallest block around them is parsed again, so every other node is
+    /// shared with the tree from before the edit. Edits outside any block, or that change which brace
+    /// closes a block, parse the whole file again. Returns the range of the new source code that was
+    /// parsed again, or an error if the edit's range isn't in the source code
+    pub fn edit(&mut self, edit: &TextEdit) -> Result<Range<usize>, String> {
+        let old_source: String = self.text();
+        let range: Range<usize> = edit.range.clone();
+        if range.start > range.end
+            || range.end > old_source.len()
+            || !old_source.is_char_boundary(range.start)
+            || !old_source.is_char_boundary(range.end)
+        {
+            return Err(format!(
+                "Edit range [{}..{}] isn't in the source code",
+                range.start, range.end
+            ));
+        }
+        let source: String = [
+            &old_source[..range.start],
+            edit.text.as_str(),
+            &old_source[range.end..],
+        ]
+        .concat();
+
+        // Try the blocks around the edit from the inside out
+        let mut node: Option<SyntaxNode> = Some(self.root().covering_node(range));
+        while let Some(block) = node {
+            if block.kind() == SyntaxKind::Block
+                && let Some(reparsed) = self.reparse_block(&block, edit, &old_source, &source)
+            {
+                return Ok(reparsed);
+            }
+            node = block.parent();
+        }
+
+        *self = SyntaxTree::parse(&source);
+        Ok(0..source.len())
+    }
+
+    /// Get the root node of the syntax tree
+    pub fn root(&self) -> SyntaxNode {
+        SyntaxNode::new_root(self.green.clone())
+    }
+
+    /// Get the typed Ast
+    pub fn ast(&self) -> &Ast {
+        &self.ast
+    }
+
+    /// Get the problems found while scanning and parsing, in the order they appear in the source code
+    pub fn diagnostics(&self) -> Diagnostics {
+        let mut diagnostics: Diagnostics = self.scanned.clone();
+        diagnostics.append(&mut self.parsed.clone());
+        diagnostics.sort();
+        diagnostics
+    }
+
+    /// Get the syntax node an Ast node was parsed from, given the Ast node's span
+    pub fn syntax(&self, span: Span) -> SyntaxNode {
+        self.root().covering_node(span.range())
+    }
+
+    /// Get the source code of the tree, exactly as written
+    pub fn text(&self) -> String {
+        self.green.to_string()
+    }
+
+    /// Utility function to parse one block again after an edit between its braces, and put it in place of
+    /// the old one. Returns the range of the new source code the block covers, or None if the block can't
+    /// be parsed on its own, leaving the tree as it was
+    fn reparse_block(
+        &mut self,
+        block: &SyntaxNode,
+        edit: &TextEdit,
+        old_source: &str,
+        source: &str,
+    ) -> Option<Range<usize>> {
+        // The block has to have been closed, which makes its closing brace one of its own children, and
+        // the edit has to be between its braces
+        let closed: bool = matches!(
+            block.green().children().last(),
+            Some(GreenElement::Token(tk)) if tk.kind == TokenKind::Token(TokenType::RightBrace)
+        );
+        let old: Vec<SyntaxToken> = block.tokens();
+        let (open, close) = (old.first()?, old.last()?);
+        if !closed || edit.range.start < open.range().end || edit.range.end > close.offset {
+            return None;
+        }
+
+        let start: Position = Position::new(1, 1, 0).after(&old_source[..open.offset]);
+        let edit_start: Position = start.after(&old_source[open.offset..edit.range.start]);
+        let shift: Shift = Shift {
+            from: edit_start.after(&old_source[edit.range.clone()]),
+            to: edit_start.after(&edit.text),
+        };
+
+        // Scan again from the last token before the edit. Tokens written right up against it are scanned
+        // again too, since the lexer may have looked past them into the edited text, and so is any token
+        // whose scan read as far as the edit, which has to be in the block
+        let mut first: usize = old
+            .iter()
+            .rposition(|tk| !tk.is_trivia() && tk.offset < edit.range.start)?;
+        while first > 0 && !old[first - 1].is_trivia() {
+            first -= 1;
+        }
+        let read_into_edit: Option<usize> = self
+            .read_past
+            .iter()
+            .filter(|read| read.start < edit.range.start && read.end >= edit.range.start)
+            .map(|read| read.start)
+            .min();
+        if let Some(offset) = read_into_edit {
+            first = first.min(old.iter().position(|tk| tk.offset == offset)?);
+        }
+        let mut lexer: Lexer = Lexer::lossless(source)
+            .starting_at(start.after(&old_source[open.offset..old[first].offset]));
+
+        // Past the edit the text is as it was, so once a piece ends where an old one did, the rest would
+        // scan the same as before. The closing brace has to be one of the old pieces that are kept
+        let mut rescanned: Vec<Piece> = Vec::new();
+        let mut resume: Option<usize> = None; // index of the first old piece that's kept after the edit
+        let mut next: usize = first;
+        'scan: for tk in lexer.by_ref() {
+            for piece in pieces(tk) {
+                let end: usize = piece.end().offset;
+                rescanned.push(piece);
+                if end < shift.to.offset {
+                    continue;
+                }
+
+                let old_end: usize = end - shift.to.offset + shift.from.offset;
+                if old_end > close.offset {
+                    return None;
+                }
+                while old[next].range().end < old_end {
+                    next += 1;
+                }
+                if old[next].range().end == old_end {
+                    resume = Some(next + 1);
+                    break 'scan;
+                }
+            }
+        }
+        let resume: usize = resume?;
+        let rescanned_end: Position = rescanned.last()?.end();
+        let mut scanned: Diagnostics = lexer.take_diagnostics();
+        scanned.retain(|d| d.span.start.offset < rescanned_end.offset);
+        let mut read_past: Vec<Range<usize>> = lexer.take_read_past();
+        read_past.retain(|read| read.start < rescanned_end.offset);
+
+        // Put the block's tokens back together, reusing the old ones around the rescanned ones
+        let mut position: Position = start;
+        let mut block_pieces: Vec<Piece> = Vec::new();
+        for tk in &old[..first] {
+            block_pieces.push(reused(tk, position, source));
+            position = position.after(tk.text());
+        }
+        block_pieces.append(&mut rescanned);
+        position = rescanned_end;
+        for tk in &old[resume..] {
+            block_pieces.push(reused(tk, position, source));
+            position = position.after(tk.text());
+        }
+        let tokens: Vec<Token> = assemble(block_pieces);
+
+        // Parentheses have to balance inside the block, before and after the edit, or the parser looking
+        // past a paren for a function's arrow could read past the block
+        let old_types = old.iter().filter_map(|tk| match tk.kind() {
+            TokenKind::Token(token_type) => Some(token_type),
+            TokenKind::Trivia(_) => None,
+        });
+        if !balanced(old_types) || !balanced(tokens.iter().map(|tk| tk.token_type)) {
+            return None;
+        }
+
+        let mut parsed: Diagnostics = Diagnostics::new();
+        let new_block: Block = parser::parse_block(tokens.clone(), &mut parsed)?;
+        let green: Arc<GreenNode> = build_block(&tokens, &new_block)?;
+
+        // Nothing can fail from here on, so the tree can be changed
+        let old_range: Range<usize> = block.range();
+        let new_range: Range<usize> = new_block.span.range();
+        let mut splice: Splice = Splice {
+            range: old_range.clone(),
+            block: Some(new_block),
+            shift: &shift,
+        };
+        splice.items(&mut self.ast.items);
+        self.green = block.replace_in_parents(green).data.green.clone();
+
+        // The block's opening brace was read by the parser around it, which can report problems there
+        let rescanned_range: Range<usize> = old[first].offset..old[resume].offset;
+        let inside_range: Range<usize> = open.range().end..old_range.end;
+        self.scanned
+            .retain(|d| !rescanned_range.contains(&d.span.start.offset));
+        self.parsed
+            .retain(|d| !inside_range.contains(&d.span.start.offset));
+        for diagnostic in self.scanned.iter_mut().chain(self.parsed.iter_mut()) {
+            shift.diagnostic(diagnostic);
+        }
+        self.scanned.append(&mut scanned);
+        self.parsed.append(&mut parsed);
+        self.read_past
+            .retain(|read| !rescanned_range.contains(&read.start));
+        for read in self.read_past.iter_mut() {
+            *read = shift.offset(read.start)..shift.offset(read.end);
+        }
+        self.read_past.append(&mut read_past);
+
+        Some(new_range)
+    }
+}
+
+// Building
+// --------
+
+/// Build the green tree of a losslessly scanned token stream, giving it the shape of its Ast. Each Ast
+/// node becomes a syntax node over the tokens in its span, and the tokens and trivia between them are
+/// added to the innermost node around them. Trivia at the edges of a node is left to its parent, so
+/// nodes start and end with the tokens the parser read
+fn build(tokens: &[Token], ast: &Ast) -> GreenNode {
+    let mut builder: Builder = Builder {
+        tokens,
+        pos: 0,
+        leading_added: false,
+        trailing: &[],
+        stack: vec![(SyntaxKind::Program, Vec::new())],
+    };
+    for item in &ast.items {
+        builder.item(item);
+    }
+
+    // Everything after the last item, down to the trivia before the end of the file
+    builder.advance_to(usize::MAX);
+    builder.flush();
+
+    let (kind, children) = builder
+        .stack
+        .pop()
+        .unwrap_or((SyntaxKind::Program, Vec::new()));
+    GreenNode::new(kind, children)
+}
+
+/// Builds a green tree by walking the Ast, adding tokens as it goes
+struct Builder<'t, 'a> {
+    tokens: &'t [Token<'a>],                     // every token, ending with EOF
+    pos: usize,                                  // index of the next token to add
+    leading_added: bool, // whether the next token's leading trivia has been added already
+    trailing: &'t [Trivia<'a>], // trivia after the last token added, not yet added to any node
+    stack: Vec<(SyntaxKind, Vec<GreenElement>)>, // the nodes being built, from the root inwards
+}
+
+impl<'t, 'a> Builder<'t, 'a> {
+    // Nodes and tokens
+    // ----------------
+
+    /// Add a node over the given span, with whatever the given function adds inside it
+    fn node(&mut self, kind: SyntaxKind, span: Span, inner: impl FnOnce(&mut Self)) {
+        // Empty nodes, like errors at the end of a line, take no trivia with them
+        self.advance_to(span.start.offset);
+        if !span.range().is_empty() {
+            self.flush();
+        }
+        self.stack.push((kind, Vec::new()));
+
+        inner(self);
+
+        self.advance_to(span.end.offset);
+        if let Some((kind, children)) = self.stack.pop() {
+            self.push(GreenElement::Node(Arc::new(GreenNode::new(kind, children))));
+        }
+    }
+
+    /// Add every token that starts before the given offset to the current node
+    fn advance_to(&mut self, offset: usize) {
+        while let Some(tk) = self.tokens.get(self.pos) {
+            if tk.token_type == TokenType::EOF || tk.span.start.offset >= offset {
+                break;
+            }
+
+            self.flush();
+            self.push(GreenElement::Token(Arc::new(GreenToken::new(
+                TokenKind::Token(tk.token_type),
+                tk.value.to_owned(),
+            ))));
+            self.trailing = &tk.trailing;
+            self.pos += 1;
+            self.leading_added = false;
+        }
+    }
+
+    /// Add the trivia waiting after the last token, and before the next one, to the current node
+    fn flush(&mut self) {
+        let trailing: &[Trivia] = std::mem::take(&mut self.trailing);
+        self.trivia(trailing);
+
+        if !self.leading_added
+            && let Some(tk) = self.tokens.get(self.pos)
+        {
+            self.trivia(&tk.leading);
+            self.leading_added = true;
+        }
+    }
+
+    /// Add pieces of trivia to the current node
+    fn trivia(&mut self, trivia: &[Trivia]) {
+        for piece in trivia {
+            self.push(GreenElement::Token(Arc::new(GreenToken::new(
+                TokenKind::Trivia(piece.kind),
+                piece.text.to_owned(),
+            ))));
+        }
+    }
+
+    /// Add a child to the current node
+    fn push(&mut self, element: GreenElement) {
+        if let Some((_, children)) = self.stack.last_mut() {
+            children.push(element);
+        }
+    }
+
+    // Ast nodes
+    // ---------
+
+    /// Add an item. Items that are statements are just their statement
+    fn item(&mut self, item: &Item) {
+        match &item.kind {
+            ItemKind::Function { function, .. } => {
+                self.node(SyntaxKind::NamedFunction, item.span, |b| {
+                    b.function(function)
+                })
+            }
+            ItemKind::Binding(binding) => self.binding(binding),
+            ItemKind::Stmt(stmt) => self.stmt(stmt),
+        }
+    }
+
+    /// Add a function literal
+    fn function(&mut self, function: &Function) {
+        self.node(SyntaxKind::Function, function.span, |b| {
+            for param in &function.params {
+                b.node(SyntaxKind::Param, param.span, |b| {
+                    if let Some(ty) = &param.ty {
+                        b.ty(ty);
+                    }
+                });
+            }
+            if let Some(ty) = &function.return_type {
+                b.ty(ty);
+            }
+            b.block(&function.body);
+        });
+    }
+
+    /// Add a block
+    fn block(&mut self, block: &Block) {
+        self.node(SyntaxKind::Block, block.span, |b| {
+            block.statements.iter().for_each(|stmt| b.stmt(stmt));
+        });
+    }
+
+    /// Add a statement. Expression statements are just their expression
+    fn stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Binding(binding) => self.binding(binding),
+            StmtKind::Assign { target, value, .. } => {
+                self.node(SyntaxKind::Assign, stmt.span, |b| {
+                    b.expr(target);
+                    b.expr(value);
+                })
+            }
+            StmtKind::Return(value) => self.node(SyntaxKind::Return, stmt.span, |b| {
+                if let Some(value) = value {
+                    b.expr(value);
+                }
+            }),
+            StmtKind::Expr(expr) => self.expr(expr),
+        }
+    }
+
+    /// Add a binding
+    fn binding(&mut self, binding: &Binding) {
+        self.node(SyntaxKind::Binding, binding.span, |b| {
+            b.pattern(&binding.pattern);
+            if let Some(ty) = &binding.ty {
+                b.ty(ty);
+            }
+            b.expr(&binding.value);
+        });
+    }
+
+    /// Add an expression
+    fn expr(&mut self, expr: &Expr) {
+        let span: Span = expr.span;
+        match &expr.kind {
+            ExprKind::Identifier(_) => self.node(SyntaxKind::Name, span, |_| {}),
+            ExprKind::Print => self.node(SyntaxKind::Print, span, |_| {}),
+            ExprKind::Literal(_) => self.node(SyntaxKind::Literal, span, |_| {}),
+            ExprKind::Array(items) => self.node(SyntaxKind::Array, span, |b| {
+                items.iter().for_each(|item| b.expr(item));
+            }),
+            ExprKind::Tuple(items) => self.node(SyntaxKind::Tuple, span, |b| {
+                items.iter().for_each(|item| b.expr(item));
+            }),
+            ExprKind::Block(block) => self.block(block),
+            ExprKind::Function(function) => self.function(function),
+            ExprKind::Unary { operand, .. } => {
+                self.node(SyntaxKind::Unary, span, |b| b.expr(operand))
+            }
+            ExprKind::Binary { left, right, .. } => self.node(SyntaxKind::Binary, span, |b| {
+                b.expr(left);
+                b.expr(right);
+            }),
+            ExprKind::Call { callee, args } => self.node(SyntaxKind::Call, span, |b| {
+                b.expr(callee);
+                args.iter().for_each(|arg| b.expr(arg));
+            }),
+            ExprKind::Index { target, index } => self.node(SyntaxKind::Index, span, |b| {
+                b.expr(target);
+                b.expr(index);
+            }),
+            ExprKind::Field { target, .. } => {
+                self.node(SyntaxKind::Field, span, |b| b.expr(target))
+            }
+            ExprKind::Path { target, .. } => self.node(SyntaxKind::Path, span, |b| b.expr(target)),
+            ExprKind::Match { subject, arms } => self.node(SyntaxKind::Match, span, |b| {
+                b.expr(subject);
+                for arm in arms {
+                    b.node(SyntaxKind::MatchArm, arm.span, |b| {
+                        b.pattern(&arm.pattern);
+                        b.expr(&arm.value);
+                    });
+                }
+            }),
+            ExprKind::Loop { condition, body } => self.node(SyntaxKind::Loop, span, |b| {
+                if let Some(condition) = condition {
+                    b.expr(condition);
+                }
+                b.block(body);
+            }),
+
+            // The embedded expressions are inside the form string's token, so they can't be nodes of their own
+            ExprKind::Interpolation(_) => self.node(SyntaxKind::Interpolation, span, |_| {}),
+            ExprKind::Error => self.node(SyntaxKind::Error, span, |_| {}),
+        }
+    }
+
+    /// Add a pattern
+    fn pattern(&mut self, pattern: &Pattern) {
+        let span: Span = pattern.span;
+        match &pattern.kind {
+            PatternKind::Wildcard => self.node(SyntaxKind::WildcardPattern, span, |_| {}),
+            PatternKind::Identifier(_) => self.node(SyntaxKind::NamePattern, span, |_| {}),
+            PatternKind::Literal { .. } => self.node(SyntaxKind::LiteralPattern, span, |_| {}),
+            PatternKind::Tuple(items) => self.node(SyntaxKind::TuplePattern, span, |b| {
+                items.iter().for_each(|item| b.pattern(item));
+            }),
+            PatternKind::Array(items) => self.node(SyntaxKind::ArrayPattern, span, |b| {
+                items.iter().for_each(|item| b.pattern(item));
+            }),
+        }
+    }
+
+    /// Add a type annotation
+    fn ty(&mut self, ty: &Type) {
+        let span: Span = ty.span;
+        match &ty.kind {
+            TypeKind::Named(_) => self.node(SyntaxKind::NamedType, span, |_| {}),
+            TypeKind::Array(item) => self.node(SyntaxKind::ArrayType, span, |b| b.ty(item)),
+            TypeKind::Tuple(items) => self.node(SyntaxKind::TupleType, span, |b| {
+                items.iter().for_each(|item| b.ty(item));
+            }),
+            TypeKind::Function { params, ret } => self.node(SyntaxKind::FunctionType, span, |b| {
+                params.iter().for_each(|param| b.ty(param));
+                b.ty(ret);
+            }),
+            TypeKind::Optional(item) => self.node(SyntaxKind::OptionalType, span, |b| b.ty(item)),
+        }
+    }
+}
+
+/// Build the green node of a single block from its tokens, which start and end with its braces
+fn build_block(tokens: &[Token], block: &Block) -> Option<Arc<GreenNode>> {
+    // The block is built inside a placeholder node, which is thrown away
+    let mut builder: Builder = Builder {
+        tokens,
+        pos: 0,
+        leading_added: false,
+        trailing: &[],
+        stack: vec![(SyntaxKind::Block, Vec::new())],
+    };
+    builder.block(block);
+
+    let (_, mut children) = builder.stack.pop()?;
+    match children.pop() {
+        Some(GreenElement::Node(node)) => Some(node),
+        _ => None,
+    }
+}
+
+// Reparsing
+// ---------
+
+/// A token or a piece of trivia. Reparsing works on them in the flat order they're written in, since
+/// whether trivia trails one token or leads the next doesn't change where it ends up in the tree
+enum Piece<'a> {
+    Token(Token<'a>),
+    Trivia(Trivia<'a>),
+}
+
+impl Piece<'_> {
+    /// Get the position just past this piece
+    fn end(&self) -> Position {
+        match self {
+            Piece::Token(tk) => tk.span.end,
+            Piece::Trivia(trivia) => trivia.span.end,
+        }
+    }
+}
+
+/// Moves positions after an edit to where the edit leaves them
+struct Shift {
+    from: Position, // the end of the replaced text
+    to: Position,   // the end of the text that replaced it
+}
+
+impl Shift {
+    /// Move a position, if it's after the edit
+    fn position(&self, position: &mut Position) {
+        if position.offset < self.from.offset {
+            return;
+        }
+        if position.line == self.from.line {
+            position.col += self.to.col - self.from.col;
+        }
+        position.line += self.to.line - self.from.line;
+        position.offset = self.offset(position.offset);
+    }
+
+    /// Move a byte offset, if it's after the edit
+    fn offset(&self, offset: usize) -> usize {
+        if offset < self.from.offset {
+            return offset;
+        }
+        offset - self.from.offset + self.to.offset
+    }
+
+    /// Move both ends of a span
+    fn span(&self, span: &mut Span) {
+        self.position(&mut span.start);
+        self.position(&mut span.end);
+    }
+
+    /// Move everything a diagnostic points at
+    fn diagnostic(&self, diagnostic: &mut Diagnostic) {
+        self.span(&mut diagnostic.span);
+        for label in &mut diagnostic.labels {
+            self.span(&mut label.span);
+        }
+    }
+}
+
+/// Puts a reparsed block in place of the old one in the Ast, moving the nodes after it to match
+struct Splice<'s> {
+    range: Range<usize>,  // where the old block was
+    block: Option<Block>, // the reparsed block, until it's been put in place
+    shift: &'s Shift,
+}
+
+impl Splice<'_> {
+    /// Splice into every item
+    fn items(&mut self, items: &mut [Item]) {
+        for item in items {
+            self.shift.span(&mut item.span);
+            match &mut item.kind {
+                ItemKind::Function { name, function } => {
+                    self.ident(name);
+                    self.function(function);
+                }
+                ItemKind::Binding(binding) => self.binding(binding),
+                ItemKind::Stmt(stmt) => self.stmt(stmt),
+            }
+        }
+    }
+
+    /// Splice into a function literal
+    fn function(&mut self, function: &mut Function) {
+        self.shift.span(&mut function.span);
+        for param in &mut function.params {
+            self.shift.span(&mut param.span);
+            self.ident(&mut param.name);
+            if let Some(ty) = &mut param.ty {
+                self.ty(ty);
+            }
+        }
+        if let Some(ty) = &mut function.return_type {
+            self.ty(ty);
+        }
+        self.block(&mut function.body);
+    }
+
+    /// Splice into a block, or replace it if it's the old block
+    fn block(&mut self, block: &mut Block) {
+        if block.span.range() == self.range
+            && let Some(reparsed) = self.block.take()
+        {
+            *block = reparsed;
+            return;
+        }
+
+        self.shift.span(&mut block.span);
+        block.statements.iter_mut().for_each(|stmt| self.stmt(stmt));
+    }
+
+    /// Splice into a statement
+    fn stmt(&mut self, stmt: &mut Stmt) {
+        self.shift.span(&mut stmt.span);
+        match &mut stmt.kind {
+            StmtKind::Binding(binding) => self.binding(binding),
+            StmtKind::Assign { target, value, .. } => {
+                self.expr(target);
+                self.expr(value);
+            }
+            StmtKind::Return(value) => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            StmtKind::Expr(expr) => self.expr(expr),
+        }
+    }
+
+    /// Splice into a binding
+    fn binding(&mut self, binding: &mut Binding) {
+        self.shift.span(&mut binding.span);
+        self.pattern(&mut binding.pattern);
+        if let Some(ty) = &mut binding.ty {
+            self.ty(ty);
+        }
+        self.expr(&mut binding.value);
+    }
+
+    /// Splice into an expression
+    fn expr(&mut self, expr: &mut Expr) {
+        self.shift.span(&mut expr.span);
+        match &mut expr.kind {
+            ExprKind::Identifier(_) | ExprKind::Print | ExprKind::Literal(_) | ExprKind::Error => {}
+            ExprKind::Array(items) | ExprKind::Tuple(items) => {
+                items.iter_mut().for_each(|item| self.expr(item));
+            }
+            ExprKind::Block(block) => self.block(block),
+            ExprKind::Function(function) => self.function(function),
+            ExprKind::Unary { operand, .. } => self.expr(operand),
+            ExprKind::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            ExprKind::Call { callee, args } => {
+                self.expr(callee);
+                args.iter_mut().for_each(|arg| self.expr(arg));
+            }
+            ExprKind::Index { target, index } => {
+                self.expr(target);
+                self.expr(index);
+            }
+            ExprKind::Field { target, name, .. } | ExprKind::Path { target, name } => {
+                self.expr(target);
+                self.ident(name);
+            }
+            ExprKind::Match { subject, arms } => {
+                self.expr(subject);
+                for arm in arms {
+                    self.shift.span(&mut arm.span);
+                    self.pattern(&mut arm.pattern);
+                    self.expr(&mut arm.value);
+                }
+            }
+            ExprKind::Loop { condition, body } => {
+                if let Some(condition) = condition {
+                    self.expr(condition);
+                }
+                self.block(body);
+            }
+            ExprKind::Interpolation(parts) => {
+                for part in parts {
+                    match part {
+                        InterpolationPart::Text(_, span) => self.shift.span(span),
+                        InterpolationPart::Expr(expr) => self.expr(expr),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Splice into a pattern
+    fn pattern(&mut self, pattern: &mut Pattern) {
+        self.shift.span(&mut pattern.span);
+        if let PatternKind::Tuple(items) | PatternKind::Array(items) = &mut pattern.kind {
+            items.iter_mut().for_each(|item| self.pattern(item));
+        }
+    }
+
+    /// Splice into a type annotation
+    fn ty(&mut self, ty: &mut Type) {
+        self.shift.span(&mut ty.span);
+        match &mut ty.kind {
+            TypeKind::Named(path) => path.iter_mut().for_each(|ident| self.ident(ident)),
+            TypeKind::Array(item) | TypeKind::Optional(item) => self.ty(item),
+            TypeKind::Tuple(items) => items.iter_mut().for_each(|item| self.ty(item)),
+            TypeKind::Function { params, ret } => {
+                params.iter_mut().for_each(|param| self.ty(param));
+                self.ty(ret);
+            }
+        }
+    }
+
+    /// Splice into a name
+    fn ident(&mut self, ident: &mut Ident) {
+        self.shift.span(&mut ident.span);
+    }
+}
+
+/// Utility function to split a token into its leading trivia, itself and its trailing trivia. The EOF
+/// token has no text, so only its trivia is kept
+fn pieces(mut tk: Token) -> Vec<Piece> {
+    let trailing: Vec<Trivia> = std::mem::take(&mut tk.trailing);
+    let mut pieces: Vec<Piece> = std::mem::take(&mut tk.leading)
+        .into_iter()
+        .map(Piece::Trivia)
+        .collect();
+    if tk.token_type != TokenType::EOF {
+        pieces.push(Piece::Token(tk));
+    }
+    pieces.extend(trailing.into_iter().map(Piece::Trivia));
+    pieces
+}
+
+/// Utility function to turn an old token of the syntax tree into a piece of the new source code, now that
+/// it starts at the given position. Literals are scanned again for their decoded values, which the syntax
+/// tree doesn't keep
+fn reused<'a>(token: &SyntaxToken, position: Position, source: &'a str) -> Piece<'a> {
+    let span: Span = Span::new(position, position.after(token.text()));
+    let text: &'a str = &source[span.range()];
+    match token.kind() {
+        TokenKind::Trivia(kind) => Piece::Trivia(Trivia::new(kind, text, span)),
+        TokenKind::Token(
+            token_type @ (TokenType::Int
+            | TokenType::Float
+            | TokenType::String
+            | TokenType::Char
+            | TokenType::FormString),
+        ) => match Lexer::new(source).starting_at(position).next() {
+            Some(tk) => Piece::Token(tk),
+            None => Piece::Token(Token::new(text, token_type, span)),
+        },
+        TokenKind::Token(token_type) => Piece::Token(Token::new(text, token_type, span)),
+    }
+}
+
+/// Utility function to put pieces back together into tokens, with the trivia before each token leading it
+fn assemble(pieces: Vec<Piece>) -> Vec<Token> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut leading: Vec<Trivia> = Vec::new();
+    for piece in pieces {
+        match piece {
+            Piece::Trivia(trivia) => leading.push(trivia),
+            Piece::Token(mut tk) => {
+                tk.leading = std::mem::take(&mut leading);
+                tk.trailing = Vec::new();
+                tokens.push(tk);
+            }
+        }
+    }
+    tokens
+}
+
+/// Utility function to tell us if every parenthesis is closed, and none is closed before it's opened
+fn balanced(token_types: impl Iterator<Item = TokenType>) -> bool {
+    let mut depth: usize = 0;
+    for token_type in token_types {
+        match token_type {
+            TokenType::LeftParen => depth += 1,
+            TokenType::RightParen if depth == 0 => return false,
+            TokenType::RightParen => depth -= 1,
+            _ => {}
+        }
+    }
+    depth == 0
+}
@@ -1,171 +1,260 @@
-use std::fmt;
+use std::{borrow::Cow, fmt};
 
 use colored::Colorize;
+use unicode_normalization::{UnicodeNormalization, is_nfc};
 
-#[derive(strum_macros::Display, PartialEq)]
+use crate::span::Span;
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(strum_macros::Display, Clone, Copy, PartialEq, Debug)]
 pub enum TokenType {
-    // Single-char tokens
-    Hash,         // #
-    Dollar,       // $
-    LeftParen,    // (
-    RightParen,   // )
-    LeftBracket,  // [
-    RightBracket, // ]
-    LeftBrace,    // {
-    RightBrace,   // }
-    Comma,        // ,
-    Question,     // ?
-    At,           // @
-    Underscore,   // _
-
-    // Single or double-char tokens by initial char
-    Bang,          // !
-    BangEquals,    // !=
-    Percent,       // %
-    PercentEquals, // %=
-    And,           // &
-    AndAnd,        // &&
-    AndEquals,     // &=
-    Star,          // *
-    StarEquals,    // *=
-    Plus,          // +
-    PlusEquals,    // +=
-    Minus,         // -
-    MinusEquals,   // -=
-    RightArrow,    // ->
-    Slash,         // /
-    SlashEquals,   // /=
-    Colon,         // :
-    ColonEquals,   // :=
-    ColonColon,    // ::
-    Equals,        // =
-    EqualsEquals,  // ==
-    EqualsArrow,   // =>
-    Caret,         // ^
-    CaretEquals,   // ^=
-    Pipe,          // |
-    PipePipe,      // ||
-    Tilde,         // ~
-    TildeEquals,   // ~=
-
-    // Single, double, or triple-char tokens by initial char
-    LeftAngle,        // <
-    LeftShift,        // <<
-    LeftArrow,        // <-
-    LessOrEquals,     // <=
-    LeftShiftEquals,  // <<=
-    RightAngle,       // >
-    GreaterOrEquals,  // >=
-    RightShift,       // >>
-    RightShiftEquals, // >>=
-    Dot,              // .
-    DotQuestion,      // .?
-    DotDotQuestion,   // ..?
+    // Operators, spelled as listed in operators::OPERATORS
+    Hash,
+    Dollar,
+    LeftParen,
+    RightParen,
+    LeftBracket,
+    RightBracket,
+    LeftBrace,
+    RightBrace,
+    Comma,
+    Question,
+    At,
+    Underscore,
+    Bang,
+    BangEquals,
+    Percent,
+    PercentEquals,
+    And,
+    AndAnd,
+    AndEquals,
+    Star,
+    StarEquals,
+    Plus,
+    PlusEquals,
+    Minus,
+    MinusEquals,
+    RightArrow,
+    Slash,
+    SlashEquals,
+    Colon,
+    ColonEquals,
+    ColonColon,
+    Equals,
+    EqualsEquals,
+    EqualsArrow,
+    Caret,
+    CaretEquals,
+    Pipe,
+    PipePipe,
+    Tilde,
+    TildeEquals,
+    LeftAngle,
+    LeftShift,
+    LeftArrow,
+    LessOrEquals,
+    LeftShiftEquals,
+    RightAngle,
+    GreaterOrEquals,
+    RightShift,
+    RightShiftEquals,
+    Dot,
+    DotQuestion,
+    DotDotQuestion,
 
     // Literals
-    Identifier, // begins with a-zA-Z
-    String,     // begins with "
+    Identifier, // a UAX #31 XID_Start char or _, then XID_Continue chars
+    String,     // begins with " or, for raw strings, r
     FormString, // begins with `
     Char,       // begins with '
-    Int,        // sequence of only 0-9
-    Float,      // sequence of only 0-9 and exactly 1 non-initial, non-final .
+    Int,        // decimal digits of any script, or 0x/0o/0b digits, with _ separators and a suffix
+    Float,      // decimal digits with a . fraction, an e exponent or an f32/f64 suffix
 
     // Other
-    Comment, // ; until end of line
-    EOF,     // end of file
+    Comment,      // ; until end of line
+    DocComment,   // ;; until end of line, documenting the item after it
+    BlockComment, // ;* until the matching *;, which can be nested
+    Error,        // source code that couldn't be scanned
+    EOF,          // end of file
+}
+
+/// The value of a literal token, decoded from its source text
+#[derive(Clone)]
+pub enum LiteralValue<'a> {
+    Int(u128, Option<NumberSuffix>),
+    Float(f64, Option<NumberSuffix>),
+    Str(Cow<'a, str>), // contents of a string, with escape sequences decoded
+    Char(char),
+    Form(Vec<FormSegment<'a>>), // pieces of a form string, in order
+}
+
+/// A type suffix on a number literal, such as the u8 in 255u8
+#[derive(strum_macros::Display, Clone, Copy, PartialEq)]
+#[strum(serialize_all = "lowercase")]
+pub enum NumberSuffix {
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    F32,
+    F64,
 }
 
-/// Returns the matching TokenType for a string representation of an operator, if applicable
-pub fn parse_op(op: &str) -> Option<TokenType> {
-    match op {
-        // Single char
-        "#" => Some(TokenType::Hash),
-        "$" => Some(TokenType::Dollar),
-        "(" => Some(TokenType::LeftParen),
-        ")" => Some(TokenType::RightParen),
-        "[" => Some(TokenType::LeftBracket),
-        "]" => Some(TokenType::RightBracket),
-        "{" => Some(TokenType::LeftBrace),
-        "}" => Some(TokenType::RightBrace),
-        "," => Some(TokenType::Comma),
-        "?" => Some(TokenType::Question),
-        "@" => Some(TokenType::At),
-        "_" => Some(TokenType::Underscore),
-
-        // Single and double-char
-        "~=" => Some(TokenType::TildeEquals),
-        "~" => Some(TokenType::Tilde),
-        "||" => Some(TokenType::PipePipe),
-        "|" => Some(TokenType::Pipe),
-        "^=" => Some(TokenType::CaretEquals),
-        "^" => Some(TokenType::Caret),
-        "=>" => Some(TokenType::EqualsArrow),
-        "==" => Some(TokenType::EqualsEquals),
-        "=" => Some(TokenType::Equals),
-        ":=" => Some(TokenType::ColonEquals),
-        "::" => Some(TokenType::ColonColon),
-        ":" => Some(TokenType::Colon),
-        "/=" => Some(TokenType::SlashEquals),
-        "/" => Some(TokenType::Slash),
-        "->" => Some(TokenType::RightArrow),
-        "-=" => Some(TokenType::MinusEquals),
-        "-" => Some(TokenType::Minus),
-        "+=" => Some(TokenType::PlusEquals),
-        "+" => Some(TokenType::Plus),
-        "*=" => Some(TokenType::StarEquals),
-        "*" => Some(TokenType::Star),
-        "&=" => Some(TokenType::AndEquals),
-        "&&" => Some(TokenType::AndAnd),
-        "&" => Some(TokenType::And),
-        "%=" => Some(TokenType::PercentEquals),
-        "%" => Some(TokenType::Percent),
-        "!=" => Some(TokenType::BangEquals),
-        "!" => Some(TokenType::Bang),
-
-        // Single, double and triple-char
-        "<<=" => Some(TokenType::LeftShiftEquals),
-        "<<" => Some(TokenType::LeftShift),
-        "<=" => Some(TokenType::LessOrEquals),
-        "<-" => Some(TokenType::LeftArrow),
-        "<" => Some(TokenType::LeftAngle),
-        ">>=" => Some(TokenType::RightShiftEquals),
-        ">>" => Some(TokenType::RightShift),
-        ">=" => Some(TokenType::GreaterOrEquals),
-        ">" => Some(TokenType::RightAngle),
-        "..?" => Some(TokenType::DotDotQuestion),
-        ".?" => Some(TokenType::DotQuestion),
-        "." => Some(TokenType::Dot),
-
-        _ => None,
+impl NumberSuffix {
+    /// Get the suffix with the given name, if there is one
+    pub fn from_name(name: &str) -> Option<NumberSuffix> {
+        match name {
+            "i8" => Some(NumberSuffix::I8),
+            "i16" => Some(NumberSuffix::I16),
+            "i32" => Some(NumberSuffix::I32),
+            "i64" => Some(NumberSuffix::I64),
+            "i128" => Some(NumberSuffix::I128),
+            "u8" => Some(NumberSuffix::U8),
+            "u16" => Some(NumberSuffix::U16),
+            "u32" => Some(NumberSuffix::U32),
+            "u64" => Some(NumberSuffix::U64),
+            "u128" => Some(NumberSuffix::U128),
+            "f32" => Some(NumberSuffix::F32),
+            "f64" => Some(NumberSuffix::F64),
+            _ => None,
+        }
+    }
+
+    /// Check if this suffix makes the literal a float
+    pub fn is_float(self) -> bool {
+        self == NumberSuffix::F32 || self == NumberSuffix::F64
     }
+
+    /// Get the largest value an integer literal with this suffix can hold. Literals are never negative,
+    /// since the minus sign is an operator, so signed types allow one past their max: that way -128i8
+    /// can be written as 128i8 negated
+    pub fn max_magnitude(self) -> u128 {
+        match self {
+            NumberSuffix::I8 => i8::MIN.unsigned_abs() as u128,
+            NumberSuffix::I16 => i16::MIN.unsigned_abs() as u128,
+            NumberSuffix::I32 => i32::MIN.unsigned_abs() as u128,
+            NumberSuffix::I64 => i64::MIN.unsigned_abs() as u128,
+            NumberSuffix::I128 => i128::MIN.unsigned_abs(),
+            NumberSuffix::U8 => u8::MAX as u128,
+            NumberSuffix::U16 => u16::MAX as u128,
+            NumberSuffix::U32 => u32::MAX as u128,
+            NumberSuffix::U64 => u64::MAX as u128,
+            NumberSuffix::U128 | NumberSuffix::F32 | NumberSuffix::F64 => u128::MAX,
+        }
+    }
+}
+
+/// A piece of a form string
+#[derive(Clone)]
+pub enum FormSegment<'a> {
+    Text(Cow<'a, str>, Span),   // literal text, with escape sequences decoded
+    Code(Vec<Token<'a>>, Span), // tokens of an embedded expression, including its braces
+}
+
+/// What a piece of trivia is made of
+#[derive(strum_macros::Display, Clone, Copy, PartialEq, Debug)]
+pub enum TriviaKind {
+    Whitespace,   // spaces, tabs, and carriage returns that don't end a line
+    Newline,      // a line break, either \n or \r\n
+    Comment,      // a ; comment, up to but not including the end of its line
+    DocComment,   // a ;; doc comment, up to but not including the end of its line
+    BlockComment, // a ;* block comment, up to and including its closing *;
 }
 
-pub struct Token {
+/// Source text between tokens that doesn't affect the meaning of the program
+#[derive(Clone)]
+pub struct Trivia<'a> {
+    pub kind: TriviaKind,
+    pub text: &'a str, // source text of the trivia, exactly as written
+    pub span: Span,
+}
+
+impl<'a> Trivia<'a> {
+    /// Create a new piece of Trivia
+    pub fn new(kind: TriviaKind, text: &'a str, span: Span) -> Trivia<'a> {
+        Trivia { kind, text, span }
+    }
+}
+
+#[derive(Clone)]
+pub struct Token<'a> {
     pub token_type: TokenType,
-    pub value: String,
-    pub line: i32,
-    pub col: i32,
+    pub value: &'a str, // source text of the token, exactly as written
+    pub span: Span,
+    pub literal: Option<LiteralValue<'a>>, // decoded value, for literal tokens
+    pub message: Option<String>,           // what went wrong, for Error tokens
+    pub leading: Vec<Trivia<'a>>, // trivia before the token, only kept when scanning losslessly
+    pub trailing: Vec<Trivia<'a>>, // trivia after the token, up to the end of its line
 }
 
-impl Token {
+impl<'a> Token<'a> {
     /// Create a new Token
-    pub fn new(value: String, token_type: TokenType, line: i32, col: i32) -> Token {
+    pub fn new(value: &'a str, token_type: TokenType, span: Span) -> Token<'a> {
         Token {
-            token_type: token_type,
-            value: value,
-            line: line,
-            col: col,
+            token_type,
+            value,
+            span,
+            literal: None,
+            message: None,
+            leading: Vec::new(),
+            trailing: Vec::new(),
         }
     }
+
+    /// Attach the decoded value of a literal to this Token
+    pub fn with_literal(mut self, literal: LiteralValue<'a>) -> Token<'a> {
+        self.literal = Some(literal);
+        self
+    }
+
+    /// Create a new Error token for source code that couldn't be scanned
+    pub fn error(value: &'a str, message: String, span: Span) -> Token<'a> {
+        Token {
+            token_type: TokenType::Error,
+            value,
+            span,
+            literal: None,
+            message: Some(message),
+            leading: Vec::new(),
+            trailing: Vec::new(),
+        }
+    }
+
+    /// Get the name an Identifier token stands for. Names are compared in NFC, so precomposed and
+    /// decomposed accents name the same thing. Names already in NFC are borrowed from the source code
+    pub fn name(&self) -> Cow<'a, str> {
+        if is_nfc(self.value) {
+            Cow::Borrowed(self.value)
+        } else {
+            Cow::Owned(self.value.nfc().collect())
+        }
+    }
+
+    /// Get the source text of this Token along with its trivia, exactly as written
+    pub fn full_text(&self) -> String {
+        let mut text: String = String::new();
+        self.leading.iter().for_each(|t| text.push_str(t.text));
+        text.push_str(self.value);
+        self.trailing.iter().for_each(|t| text.push_str(t.text));
+        text
+    }
 }
 
 /// Allow pretty-printing of Tokens
-impl fmt::Display for Token {
+impl fmt::Display for Token<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "{} {} ({}, {})",
-            self.value.bold(), format!("{}", self.token_type).italic(), self.line, self.col
+            "{} {} ({})",
+            self.value.bold(),
+            format!("{}", self.token_type).italic(),
+            self.span
         )
     }
 }
@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+use unicode_xid::UnicodeXID;
+
+use crate::{
+    diagnostic::Diagnostics,
+    dialect::Dialect,
+    lexer::Lexer,
+    operators::{OPERATORS, STANDARD},
+    span::{Position, Span},
+    token::{FormSegment, LiteralValue, Token},
+};
+
+/// Canonical Rumil source code transpiled from a dialect, along with where each part of it came from
+pub struct Transpiled {
+    pub code: String,          // the canonical Rumil source code
+    pub source_map: SourceMap, // relates the code back to the dialect source code
+}
+
+/// Relates every span of transpiled Rumil code to the span of dialect source code it came from
+#[derive(Serialize, Deserialize, Default)]
+pub struct SourceMap {
+    pub mappings: Vec<Mapping>, // in order, covering the transpiled code from start to end
+}
+
+/// A piece of transpiled code and the dialect source code it came from. Text that was copied over
+/// unchanged is one mapping, and each dialect keyword or symbol rewritten into Rumil is another
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct Mapping {
+    pub generated: Span, // where the piece is in the transpiled code
+    pub original: Span,  // where it came from in the dialect source code
+}
+
+impl SourceMap {
+    /// Read a source map from the JSON it was written out as
+    pub fn from_json(json: &str) -> Result<SourceMap, String> {
+        serde_json::from_str(json).map_err(|err| format!("Invalid source map: {}", err))
+    }
+
+    /// Write this source map out as JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// Take a span of the transpiled code back to the dialect source code it came from
+    pub fn original_span(&self, span: Span) -> Span {
+        Span::new(
+            self.original_position(span.start, false),
+            self.original_position(span.end, true),
+        )
+    }
+
+    /// Take every diagnostic reported against the transpiled code back to the dialect source code, so
+    /// it points at what the user actually wrote
+    pub fn remap(&self, diagnostics: &mut Diagnostics) {
+        for diagnostic in diagnostics.iter_mut() {
+            diagnostic.span = self.original_span(diagnostic.span);
+            for label in diagnostic.labels.iter_mut() {
+                label.span = self.original_span(label.span);
+            }
+        }
+    }
+
+    /// Take a position in the transpiled code back to the dialect source code. A position between two
+    /// mappings belongs to the one after it when it starts a span, and to the one before it when it ends one
+    fn original_position(&self, position: Position, is_end: bool) -> Position {
+        let mapping: Option<&Mapping> = if is_end {
+            let i: usize = self
+                .mappings
+                .partition_point(|m| m.generated.end.offset < position.offset);
+            self.mappings.get(i).or(self.mappings.last())
+        } else {
+            let i: usize = self
+                .mappings
+                .partition_point(|m| m.generated.start.offset <= position.offset);
+            self.mappings.get(i.saturating_sub(1))
+        };
+        let Some(mapping) = mapping else {
+            return position;
+        };
+        let (generated, original) = (mapping.generated, mapping.original);
+
+        // Rewritten keywords and symbols can change length, so positions inside them snap to either end
+        if generated.range().len() != original.range().len() {
+            return if is_end { original.end } else { original.start };
+        }
+
+        // Copied text is the same on both sides, so only the lines and columns before it differ
+        let offset: usize =
+            original.start.offset + position.offset.saturating_sub(generated.start.offset);
+        if position.line == generated.start.line {
+            Position::new(
+                original.start.line,
+                original.start.col + position.col - generated.start.col,
+                offset,
+            )
+        } else {
+            Position::new(
+                original.start.line + position.line - generated.start.line,
+                position.col,
+                offset,
+            )
+        }
+    }
+}
+
+/// Transpile source code written in a dialect into canonical Rumil. Every dialect keyword and symbol is
+/// rewritten as the Rumil operator it stands for, and everything else, including comments and formatting,
+/// is copied over as it is. Problems in the source code are left for scanning the transpiled code to
+/// report, and the source map takes them back to the dialect source code
+pub fn transpile(source_code: &str, dialect: &Dialect) -> Transpiled {
+    let tokens: Vec<Token> = Lexer::new(source_code).with_dialect(dialect).collect();
+    let mut flat: Vec<&Token> = Vec::new();
+    flatten(&tokens, &mut flat);
+
+    // Decide what each token is written as in Rumil, spacing out tokens that would otherwise run together
+    let mut rewrites: Vec<(Span, String)> = Vec::new();
+    for (i, token) in flat.iter().enumerate() {
+        let canonical: Option<&str> = canonical_symbol(token);
+        let next: Option<&&Token> = flat
+            .get(i + 1)
+            .filter(|next| next.span.start.offset == token.span.end.offset);
+        let mut text: String = canonical.unwrap_or(token.value).to_owned();
+
+        if let Some(next) = next {
+            let next_canonical: Option<&str> = canonical_symbol(next);
+            if (canonical.is_some() || next_canonical.is_some())
+                && runs_together(&text, next_canonical.unwrap_or(next.value))
+            {
+                text.push(' ');
+            }
+        }
+
+        if text != token.value {
+            rewrites.push((token.span, text));
+        }
+    }
+
+    // Copy the source code over, swapping in the rewrites and mapping each piece back to where it came from
+    let mut code: String = String::with_capacity(source_code.len());
+    let mut source_map: SourceMap = SourceMap::default();
+    let mut generated: Position = Position::new(1, 1, 0);
+    let mut original: Position = Position::new(1, 1, 0);
+    for (span, text) in rewrites {
+        let copied: &str = &source_code[original.offset..span.start.offset];
+        generated = append(
+            &mut code,
+            &mut source_map,
+            copied,
+            generated,
+            Span::new(original, span.start),
+        );
+        generated = append(&mut code, &mut source_map, &text, generated, span);
+        original = span.end;
+    }
+    let rest: &str = &source_code[original.offset..];
+    append(
+        &mut code,
+        &mut source_map,
+        rest,
+        generated,
+        Span::new(original, original.after(rest)),
+    );
+
+    Transpiled { code, source_map }
+}
+
+/// Utility function to list every token in the order it appears, including the tokens inside form strings
+fn flatten<'t, 'a>(tokens: &'t [Token<'a>], flat: &mut Vec<&'t Token<'a>>) {
+    for token in tokens {
+        match &token.literal {
+            Some(LiteralValue::Form(segments)) => {
+                for segment in segments {
+                    if let FormSegment::Code(code, _) = segment {
+                        flatten(code, flat);
+                    }
+                }
+            }
+            _ => flat.push(token),
+        }
+    }
+}
+
+/// Utility function to get how Rumil spells a token, if the dialect spells it differently
+fn canonical_symbol(token: &Token) -> Option<&'static str> {
+    OPERATORS
+        .iter()
+        .find(|operator| operator.token_type == token.token_type)
+        .map(|operator| operator.symbol)
+        .filter(|symbol| *symbol != token.value)
+}
+
+/// Utility function to tell us if two tokens written right next to each other would scan as something else
+fn runs_together(left: &str, right: &str) -> bool {
+    if left == "_" {
+        return right.starts_with(|c: char| c.is_xid_continue());
+    }
+    STANDARD.get(left).is_some()
+        && STANDARD
+            .longest_match(&format!("{}{}", left, right))
+            .is_some_and(|(_, len)| len > left.len())
+}
+
+/// Utility function to add a piece of transpiled code, mapped to where it came from, and get where the
+/// transpiled code ends after it
+fn append(
+    code: &mut String,
+    source_map: &mut SourceMap,
+    text: &str,
+    start: Position,
+    original: Span,
+) -> Position {
+    if text.is_empty() && original.range().is_empty() {
+        return start;
+    }
+
+    let end: Position = start.after(text);
+    code.push_str(text);
+    source_map.mappings.push(Mapping {
+        generated: Span::new(start, end),
+        original,
+    });
+    end
+}
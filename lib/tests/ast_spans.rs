@@ -0,0 +1,86 @@
+use rumil_parser::{
+    ast::{Ast, ExprKind, ItemKind, StmtKind},
+    diagnostic::Diagnostics,
+    lexer::scan,
+    span::Span,
+};
+
+/// Source code with a documented function and a typed binding that calls it
+const SOURCE: &str =
+    ";; Adds\nadd := (a: int, b: [str]) -> int {\n    -> a + b\n}\nx: int := add(1, 2)\n";
+
+/// Utility function to parse the source code above
+fn parse() -> Box<Ast> {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let tokens = scan(SOURCE, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+    let ast: Box<Ast> = Ast::new(tokens, &mut diagnostics);
+    assert!(diagnostics.is_empty());
+    ast
+}
+
+/// Utility function to get the source text a span covers
+fn text(span: Span) -> &'static str {
+    &SOURCE[span.range()]
+}
+
+/// Items cover their source code without the doc comments above them, and know their lines and columns
+#[test]
+fn items_span_their_source_code() {
+    let ast: Box<Ast> = parse();
+    assert_eq!(ast.items[0].span.to_string(), "2:1-4:2");
+    assert_eq!(ast.items[0].docs, ["Adds"]);
+    assert_eq!(ast.items[1].span.to_string(), "5:1-5:20");
+    assert_eq!(text(ast.items[1].span), "x: int := add(1, 2)");
+}
+
+/// Every part of a function has the span of just the source code it was parsed from
+#[test]
+fn function_parts_have_spans() {
+    let ast: Box<Ast> = parse();
+    let ItemKind::Function { name, function } = &ast.items[0].kind else {
+        panic!("Expected a function");
+    };
+    assert_eq!(text(name.span), "add");
+    assert!(text(function.span).starts_with("(a: int"));
+
+    let params: Vec<(&str, &str)> = function
+        .params
+        .iter()
+        .map(|param| (text(param.span), text(param.ty.as_ref().unwrap().span)))
+        .collect();
+    assert_eq!(params, [("a: int", "int"), ("b: [str]", "[str]")]);
+    assert_eq!(text(function.return_type.as_ref().unwrap().span), "int");
+    assert_eq!(text(function.body.span), "{\n    -> a + b\n}");
+
+    let statement = &function.body.statements[0];
+    assert_eq!(text(statement.span), "-> a + b");
+    let StmtKind::Return(Some(value)) = &statement.kind else {
+        panic!("Expected a return");
+    };
+    let ExprKind::Binary { left, right, .. } = &value.kind else {
+        panic!("Expected a binary expression");
+    };
+    assert_eq!(
+        [text(value.span), text(left.span), text(right.span)],
+        ["a + b", "a", "b"]
+    );
+}
+
+/// Bindings have spans for their pattern, type and value, and calls for their callee and arguments
+#[test]
+fn binding_parts_have_spans() {
+    let ast: Box<Ast> = parse();
+    let ItemKind::Binding(binding) = &ast.items[1].kind else {
+        panic!("Expected a binding");
+    };
+    assert_eq!(text(binding.pattern.span), "x");
+    assert_eq!(text(binding.ty.as_ref().unwrap().span), "int");
+    assert_eq!(text(binding.value.span), "add(1, 2)");
+
+    let ExprKind::Call { callee, args } = &binding.value.kind else {
+        panic!("Expected a call");
+    };
+    let args: Vec<&str> = args.iter().map(|arg| text(arg.span)).collect();
+    assert_eq!(text(callee.span), "add");
+    assert_eq!(args, ["1", "2"]);
+}
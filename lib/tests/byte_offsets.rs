@@ -0,0 +1,47 @@
+use rumil_parser::{
+    diagnostic::Diagnostics,
+    lexer::scan,
+    span::{Position, Span},
+    token::Token,
+};
+
+/// Utility function to scan source code that has no problems in it
+fn scan_ok(source_code: &str) -> Vec<Token<'_>> {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let tokens: Vec<Token> = scan(source_code, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+    assert!(diagnostics.is_empty());
+    tokens
+}
+
+/// Every token's byte range slices its own text out of the source code, even around multi-byte chars
+#[test]
+fn token_spans_slice_the_source_code() {
+    let source_code: &str = "größe := \"😀 ü\" ; ✓\r\nx := größe + 1\n";
+    for tk in scan_ok(source_code) {
+        assert_eq!(&source_code[tk.span.range()], tk.value);
+    }
+}
+
+/// Columns count chars while offsets count bytes
+#[test]
+fn columns_count_chars_and_offsets_count_bytes() {
+    let tokens: Vec<Token> = scan_ok("größe := 1\n名 := 2\n");
+    assert_eq!(tokens[0].span.end, Position::new(1, 6, 7));
+    assert_eq!(tokens[1].span.start, Position::new(1, 7, 8));
+    assert_eq!(tokens[2].span.start, Position::new(1, 10, 11));
+
+    // The second line starts after the 13 bytes of the first
+    assert_eq!(tokens[3].value, "名");
+    assert_eq!(
+        tokens[3].span,
+        Span::new(Position::new(2, 1, 13), Position::new(2, 2, 16))
+    );
+}
+
+/// Moving a position past some text counts lines, columns and bytes the way the lexer does
+#[test]
+fn positions_move_past_text() {
+    let start: Position = Position::new(3, 5, 20);
+    assert_eq!(start.after("ab"), Position::new(3, 7, 22));
+    assert_eq!(start.after("é\nxy"), Position::new(4, 3, 25));
+}
@@ -0,0 +1,76 @@
+use rumil_parser::{
+    ast::Ast,
+    diagnostic::{Diagnostic, Diagnostics, Severity},
+    lexer::scan,
+    token::{Token, TokenType},
+};
+
+/// Utility function to scan and parse source code, returning its tokens, items and every problem found
+fn parse(source_code: &str) -> (Vec<(String, TokenType)>, Box<Ast>, Diagnostics) {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let tokens: Vec<Token> = scan(source_code, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+    let scanned: Vec<(String, TokenType)> = tokens
+        .iter()
+        .map(|tk| (tk.value.to_owned(), tk.token_type))
+        .collect();
+    let ast: Box<Ast> = Ast::new(tokens, &mut diagnostics);
+    (scanned, ast, diagnostics)
+}
+
+/// Block comments can sit in the middle of a line or span several, and the code around them is unaffected
+#[test]
+fn block_comments_are_skipped() {
+    let (tokens, ast, diagnostics) = parse("x := 1 ;* inline *; + 2\n;* multi\nline *;\ny := 1\n");
+    assert!(diagnostics.is_empty());
+    let comments: Vec<&str> = tokens
+        .iter()
+        .filter(|(_, token_type)| *token_type == TokenType::BlockComment)
+        .map(|(value, _)| value.as_str())
+        .collect();
+    assert_eq!(comments, [";* inline *;", ";* multi\nline *;"]);
+
+    let items: Vec<String> = ast.items.iter().map(|item| item.to_string()).collect();
+    assert_eq!(items, ["x := (1 + 2)", "y := 1"]);
+}
+
+/// Block comments nest, so each ;* needs its own *;
+#[test]
+fn block_comments_nest() {
+    let (tokens, ast, diagnostics) = parse(";* a ;* b *; c *;\nx := 1\n");
+    assert!(diagnostics.is_empty());
+    assert_eq!(tokens[0].0, ";* a ;* b *; c *;");
+    assert_eq!(ast.items.len(), 1);
+}
+
+/// An unclosed block comment runs to the end of the file, pointing at the comment left open inside it
+#[test]
+fn unclosed_block_comments_are_reported() {
+    let (tokens, _, diagnostics) = parse(";* a ;* b\nx\n");
+    assert_eq!(tokens.len(), 1);
+    let problems: Vec<&Diagnostic> = diagnostics.iter().collect();
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0].code.as_deref(), Some("R0015"));
+    assert_eq!(problems[0].span.to_string(), "1:1-1:3");
+    assert_eq!(problems[0].labels[0].span.to_string(), "1:6-1:8");
+}
+
+/// Doc comments attach to the item after them, with their ;; and one space taken off
+#[test]
+fn doc_comments_attach_to_items() {
+    let (_, ast, diagnostics) = parse(";; Adds two\n;;numbers\nadd := (a, b) -> { -> a + b }\n");
+    assert!(diagnostics.is_empty());
+    assert_eq!(ast.items[0].docs, ["Adds two", "numbers"]);
+    assert!(ast.items[0].span.to_string().starts_with("3:1"));
+}
+
+/// Doc comments with nothing after them to document are warned about
+#[test]
+fn unattached_doc_comments_are_warned_about() {
+    let (_, ast, diagnostics) = parse("x := 1\n;; dangling\n");
+    assert!(ast.items[0].docs.is_empty());
+    let problems: Vec<&Diagnostic> = diagnostics.iter().collect();
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0].severity, Severity::Warning);
+    assert_eq!(problems[0].code.as_deref(), Some("R0109"));
+    assert_eq!(problems[0].span.to_string(), "2:1-2:12");
+}
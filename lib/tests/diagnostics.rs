@@ -0,0 +1,94 @@
+use rumil_parser::{
+    ast::Ast,
+    diagnostic::{Diagnostic, Diagnostics, Severity},
+    lexer::scan,
+    span::{Position, Span},
+};
+
+/// Utility function to make a one line span starting at the given byte offset
+fn span_at(offset: usize, len: usize) -> Span {
+    let col: i32 = offset as i32 + 1;
+    Span::new(
+        Position::new(1, col, offset),
+        Position::new(1, col + len as i32, offset + len),
+    )
+}
+
+/// Diagnostics are built up with a code, labels, notes and help
+#[test]
+fn diagnostics_are_built_up() {
+    let diagnostic: Diagnostic = Diagnostic::error("Unclosed block".to_owned(), span_at(9, 1))
+        .with_code("R0105")
+        .with_label(span_at(0, 1), "opened here".to_owned())
+        .with_note("Blocks run until their closing brace".to_owned())
+        .with_help("Add a closing brace".to_owned());
+    assert_eq!(diagnostic.severity, Severity::Error);
+    assert_eq!(diagnostic.code.as_deref(), Some("R0105"));
+    assert_eq!(diagnostic.labels[0].message, "opened here");
+    assert_eq!(diagnostic.labels[0].span, span_at(0, 1));
+    assert_eq!(diagnostic.notes, ["Blocks run until their closing brace"]);
+    assert_eq!(diagnostic.help.as_deref(), Some("Add a closing brace"));
+
+    let warning: Diagnostic = Diagnostic::warning("Unused".to_owned(), span_at(0, 1));
+    assert_eq!(warning.severity, Severity::Warning);
+    assert!(warning.code.is_none() && warning.help.is_none());
+}
+
+/// The sink counts diagnostics by severity, and only errors count as errors
+#[test]
+fn sinks_count_by_severity() {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    diagnostics.push(Diagnostic::warning("a".to_owned(), span_at(0, 1)));
+    assert!(!diagnostics.has_errors());
+
+    let mut other: Diagnostics = Diagnostics::new();
+    other.push(Diagnostic::error("b".to_owned(), span_at(1, 1)));
+    other.push(Diagnostic::error("c".to_owned(), span_at(2, 1)));
+    diagnostics.append(&mut other);
+    assert!(other.is_empty());
+
+    assert!(diagnostics.has_errors());
+    assert_eq!(diagnostics.len(), 3);
+    assert_eq!(diagnostics.count(Severity::Error), 2);
+    assert_eq!(diagnostics.count(Severity::Warning), 1);
+    assert_eq!(diagnostics.count(Severity::Note), 0);
+}
+
+/// Sorting puts diagnostics in source order, keeping the order of ones at the same place
+#[test]
+fn sorting_is_by_position_and_stable() {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    for (message, offset) in [("c", 8), ("a", 2), ("b1", 5), ("b2", 5)] {
+        diagnostics.push(Diagnostic::error(message.to_owned(), span_at(offset, 1)));
+    }
+    diagnostics.sort();
+    let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(messages, ["a", "b1", "b2", "c"]);
+}
+
+/// The lexer and parser report into the same sink, with a code on every problem
+#[test]
+fn passes_report_structured_diagnostics() {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let tokens = scan(
+        "x := 1 £\ny := )\n",
+        &"test.rum".to_owned(),
+        &mut diagnostics,
+    )
+    .unwrap();
+    Ast::new(tokens, &mut diagnostics);
+
+    let codes: Vec<&str> = diagnostics
+        .iter()
+        .filter_map(|d| d.code.as_deref())
+        .collect();
+    assert_eq!(codes, ["R0003", "R0102"]);
+    assert_eq!(
+        diagnostics.iter().next().unwrap().message,
+        "Unrecognized character [£]"
+    );
+    assert_eq!(
+        diagnostics.iter().nth(1).unwrap().span.to_string(),
+        "2:6-2:7"
+    );
+}
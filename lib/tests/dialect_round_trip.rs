@@ -0,0 +1,177 @@
+use rumil_parser::{
+    ast::Ast, diagnostic::Diagnostics, dialect::Dialect, lexer::scan, printer::print_program,
+    transpile::transpile,
+};
+
+/// A Spanish-flavored dialect, with keywords for most operators
+const ESPAÑOL: &str = r##"
+name = "Español"
+
+[keywords]
+imprimir = "$"
+mientras = "@"
+devolver = "->"
+y = "&&"
+o = "||"
+no = "!"
+"según" = "?"
+cualquiera = "_"
+largo = "#"
+
+[operators]
+"≠" = "!="
+"≤" = "<="
+"≥" = ">="
+"←" = ":="
+"⇒" = "=>"
+"##;
+
+/// A French-flavored dialect, with words even for some punctuation
+const FRANÇAIS: &str = r#"{
+    "name": "Français",
+    "keywords": {
+        "afficher": "Dollar",
+        "tantque": "At",
+        "retourner": "RightArrow",
+        "et": "AndAnd",
+        "ou": "PipePipe",
+        "non": "Bang",
+        "selon": "Question",
+        "vaut": "ColonEquals",
+        "plus": "Plus",
+        "moins": "Minus"
+    },
+    "operators": { "≠": "BangEquals", "«": "LeftAngle" }
+}"#;
+
+/// Canonical Rumil using every kind of item, statement, expression, pattern and type
+const PROGRAM: &str = r#";; Adds two numbers.
+;; Returns their sum.
+add := (a: int, b: int) -> int {
+    -> a + b
+}
+total: int := add(1, 2) * -3
+(first, _) := (total, 'x')
+[p, q] := [1.5, -2.25e-3]
+single := (total,)
+names: [str] := ["uno", "dos\ttres"]
+point: (int, ?float) := (1, 2.0f32)
+callback: (int) -> int := (n) -> { -> n * 2 }
+i := 0
+@ i <= 10 && !(i != 5) || i == 7 {
+    $(`valor {i + 1} \{literal\}`)
+    i += 1
+    i <<= 2
+    (-i)
+    queue <- i
+}
+@ {
+    -> 
+}
+size := #names - (1 - 2) - 3
+grouped := (a ..? b) ..? c ..? d
+kind := total ? {
+    -1 => "minus one"
+    0 => "zero"
+    (1, _) => "pair"
+    _ => total ? { 2 => "two", _ => "many" }
+}
+value := math::pi.digits.?first[0](~mask & 0xff ^ 1 << 2 | 3 % 4 / 5 >> 1)
+(kind ? { _ => 1 }) + 1
+@ ({ done }) { done = done ~= pattern }
+"#;
+
+/// Utility function to parse source code, transpiling it from a dialect first if it's written in one
+fn parse(source_code: &str, dialect: Option<&Dialect>) -> Box<Ast> {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let code: String = match dialect {
+        Some(dialect) => transpile(source_code, dialect).code,
+        None => source_code.to_owned(),
+    };
+    let tokens = scan(&code, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+    let ast: Box<Ast> = Ast::new(tokens, &mut diagnostics);
+    let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert!(messages.is_empty(), "{:?} in:\n{}", messages, code);
+    ast
+}
+
+/// Utility function to describe what a program means, independent of how it's spelled or laid out
+fn meaning(ast: &Ast) -> Vec<String> {
+    ast.items.iter().map(|item| item.to_string()).collect()
+}
+
+/// Canonical Rumil printed back out parses to the same program
+#[test]
+fn canonical_round_trip() {
+    let ast: Box<Ast> = parse(PROGRAM, None);
+    let printed: String = print_program(&ast, None).unwrap();
+    let reparsed: Box<Ast> = parse(&printed, None);
+    assert_eq!(
+        meaning(&ast),
+        meaning(&reparsed),
+        "printed as:\n{}",
+        printed
+    );
+    assert_eq!(print_program(&reparsed, None).unwrap(), printed);
+}
+
+/// Rumil printed in a dialect reads back as the same program, and printing it again changes nothing
+#[test]
+fn rumil_to_dialect_round_trip() {
+    for dialect in [
+        Dialect::from_toml(ESPAÑOL).unwrap(),
+        Dialect::from_json(FRANÇAIS).unwrap(),
+    ] {
+        let ast: Box<Ast> = parse(PROGRAM, None);
+        let printed: String = print_program(&ast, Some(&dialect)).unwrap();
+        let reparsed: Box<Ast> = parse(&printed, Some(&dialect));
+        assert_eq!(
+            meaning(&ast),
+            meaning(&reparsed),
+            "printed as:\n{}",
+            printed
+        );
+        assert_eq!(print_program(&reparsed, Some(&dialect)).unwrap(), printed);
+    }
+}
+
+/// Dialect source code transpiled to Rumil and printed back in the dialect keeps its meaning
+#[test]
+fn dialect_to_rumil_to_dialect_round_trip() {
+    let dialect: Dialect = Dialect::from_toml(ESPAÑOL).unwrap();
+    let source_code: &str = "\
+;; Cuenta hasta diez
+contar ← (hasta: int) -> int {
+    i ← 0
+    mientras i ≤ hasta y no(i ≠ 5) {
+        imprimir(`valor {i según { 3 ⇒ \"tres\", cualquiera ⇒ i }}`)
+        i += 1
+    }
+    devolver largo(lista)
+}
+";
+    let original: Box<Ast> = parse(source_code, Some(&dialect));
+    let rumil: String = print_program(&original, None).unwrap();
+    let printed: String = print_program(&parse(&rumil, None), Some(&dialect)).unwrap();
+    let reparsed: Box<Ast> = parse(&printed, Some(&dialect));
+    assert_eq!(
+        meaning(&original),
+        meaning(&reparsed),
+        "printed as:\n{}",
+        printed
+    );
+    assert!(
+        printed.contains("mientras i ≤ hasta y no"),
+        "printed as:\n{}",
+        printed
+    );
+}
+
+/// Names that a dialect reads as keywords can't be printed in it
+#[test]
+fn keyword_names_are_rejected() {
+    let dialect: Dialect = Dialect::from_toml(ESPAÑOL).unwrap();
+    let ast: Box<Ast> = parse("y := 1\n", None);
+    let err: String = print_program(&ast, Some(&dialect)).unwrap_err();
+    assert!(err.contains("[y]"), "{}", err);
+}
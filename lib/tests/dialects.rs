@@ -0,0 +1,176 @@
+use std::{env::temp_dir, fs, path::PathBuf};
+
+use rumil_parser::{
+    diagnostic::Diagnostics,
+    dialect::Dialect,
+    lexer::scan_dialect,
+    token::{Token, TokenType},
+};
+
+/// A Spanish-flavored dialect, written as TOML
+const ESPAÑOL: &str = r#"
+name = "Español"
+
+[keywords]
+imprimir = "$"
+mientras = "At"
+"según" = "?"
+
+[operators]
+"≠" = "!="
+"←" = "ColonEquals"
+"#;
+
+/// The same dialect, written as JSON
+const ESPAÑOL_JSON: &str = r#"{
+    "name": "Español",
+    "keywords": { "imprimir": "$", "mientras": "At", "según": "?" },
+    "operators": { "≠": "!=", "←": "ColonEquals" }
+}"#;
+
+/// Utility function to describe how a dialect maps some words and operators
+fn describe(dialect: &Dialect) -> Vec<String> {
+    let mut mappings: Vec<String> = ["imprimir", "mientras", "según", "print"]
+        .iter()
+        .map(|word| match dialect.keyword(word) {
+            Some(token_type) => format!("{} {}", word, token_type),
+            None => format!("{} -", word),
+        })
+        .collect();
+    mappings.extend(
+        [TokenType::Dollar, TokenType::BangEquals, TokenType::Plus]
+            .iter()
+            .map(|token_type| format!("{} {:?}", token_type, dialect.spelling(*token_type))),
+    );
+    mappings
+}
+
+/// Dialects read the same from TOML and JSON, and operators can be named by their symbol or token type
+#[test]
+fn dialects_load_from_toml_and_json() {
+    let from_toml: Dialect = Dialect::from_toml(ESPAÑOL).unwrap();
+    let from_json: Dialect = Dialect::from_json(ESPAÑOL_JSON).unwrap();
+    assert_eq!(from_toml.name, "Español");
+    assert_eq!(describe(&from_toml), describe(&from_json));
+    assert_eq!(
+        describe(&from_toml),
+        [
+            "imprimir Dollar",
+            "mientras At",
+            "según Question",
+            "print -",
+            "Dollar Some(\"imprimir\")",
+            "BangEquals Some(\"≠\")",
+            "Plus None"
+        ]
+    );
+
+    // Keywords are looked up in NFC, whichever way they're written
+    assert_eq!(from_toml.keyword("segu\u{301}n"), Some(TokenType::Question));
+}
+
+/// Dialect files are read as TOML or JSON according to their extension
+#[test]
+fn dialect_files_are_loaded() {
+    let directory: PathBuf = temp_dir().join(format!("rumil-dialects-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let toml_path: PathBuf = directory.join("español.toml");
+    let json_path: PathBuf = directory.join("español.json");
+    let text_path: PathBuf = directory.join("español.txt");
+    fs::write(&toml_path, ESPAÑOL).unwrap();
+    fs::write(&json_path, ESPAÑOL_JSON).unwrap();
+    fs::write(&text_path, ESPAÑOL).unwrap();
+
+    assert_eq!(Dialect::load(&toml_path).unwrap().name, "Español");
+    assert_eq!(Dialect::load(&json_path).unwrap().name, "Español");
+    assert_eq!(
+        Dialect::load(&text_path).err().unwrap(),
+        format!(
+            "Dialect {} isn't a .toml or .json file",
+            text_path.display()
+        )
+    );
+    assert!(
+        Dialect::load(&directory.join("missing.toml"))
+            .err()
+            .unwrap()
+            .starts_with("Error reading ")
+    );
+
+    // Problems in the file say which file they're in
+    fs::write(&toml_path, "name = \"Malo\"\n[keywords]\nx = \"nada\"\n").unwrap();
+    assert_eq!(
+        Dialect::load(&toml_path).err().unwrap(),
+        format!("[nada] isn't a Rumil operator in {}", toml_path.display())
+    );
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+/// Mappings that the lexer couldn't scan, or that don't lead to a Rumil operator, are rejected
+#[test]
+fn invalid_dialects_are_rejected() {
+    for (text, message) in [
+        (
+            "name = \"Malo\"\n[keywords]\n\"dos palabras\" = \"$\"\n",
+            "Keyword [dos palabras] has to be spelled like an identifier",
+        ),
+        (
+            "name = \"Malo\"\n[keywords]\n\"1x\" = \"$\"\n",
+            "Keyword [1x] has to be spelled like an identifier",
+        ),
+        (
+            "name = \"Malo\"\n[keywords]\nimprimir = \"print\"\n",
+            "[print] isn't a Rumil operator",
+        ),
+        (
+            "name = \"Malo\"\n[operators]\n\"≠\" = \"Bang Equals\"\n",
+            "[Bang Equals] isn't a Rumil operator",
+        ),
+    ] {
+        assert_eq!(Dialect::from_toml(text).err().unwrap(), message);
+    }
+
+    // Files that aren't shaped like a dialect say where the problem is
+    let message: String = Dialect::from_toml("name = \"Malo\"\n\n[palabras]\nx = \"$\"\n")
+        .err()
+        .unwrap();
+    assert!(message.starts_with("Invalid dialect: "));
+    assert!(message.ends_with(" at line 3"));
+    assert!(
+        Dialect::from_json("{ \"keywords\": {} }")
+            .err()
+            .unwrap()
+            .starts_with("Invalid dialect: missing field `name`")
+    );
+}
+
+/// Dialect source code scans into standard Rumil tokens, with each token keeping the dialect's spelling
+#[test]
+fn dialects_scan_as_rumil() {
+    let dialect: Dialect = Dialect::from_toml(ESPAÑOL).unwrap();
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let tokens: Vec<Token> = scan_dialect(
+        "x ← 1\nimprimir x ≠ 2\n",
+        &"test.rum".to_owned(),
+        &dialect,
+        &mut diagnostics,
+    )
+    .unwrap();
+    assert!(diagnostics.is_empty());
+    let scanned: Vec<String> = tokens
+        .iter()
+        .map(|tk| format!("{} {}", tk.token_type, tk.value))
+        .collect();
+    assert_eq!(
+        scanned,
+        [
+            "Identifier x",
+            "ColonEquals ←",
+            "Int 1",
+            "Dollar imprimir",
+            "Identifier x",
+            "BangEquals ≠",
+            "Int 2"
+        ]
+    );
+}
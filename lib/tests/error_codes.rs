@@ -0,0 +1,92 @@
+use std::{fs, path::PathBuf};
+
+use rumil_parser::codes::{self, ErrorCode, explain, registry};
+
+/// Utility function to read the table of codes in the CLI docs, as pairs of code and description
+fn documented_codes() -> Vec<(String, String)> {
+    let path: PathBuf = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../docs/cli/README.md");
+    let docs: String = fs::read_to_string(path).unwrap();
+    docs.lines()
+        .filter_map(|line| {
+            let cells: Vec<&str> = line.split('|').map(str::trim).collect();
+            let code: &str = cells.get(1)?.strip_prefix("`R")?.strip_suffix('`')?;
+            Some((format!("R{}", code), cells.get(2)?.to_string()))
+        })
+        .collect()
+}
+
+/// Every registered code has a title and an explanation with an example, and no code is registered twice
+#[test]
+fn every_code_is_explained() {
+    let entries: &[ErrorCode] = registry();
+    assert!(!entries.is_empty());
+    for (i, entry) in entries.iter().enumerate() {
+        assert!(!entry.title.is_empty(), "{} has no title", entry.code);
+        assert!(
+            entry.explanation.contains("\n    "),
+            "{} has no example",
+            entry.code
+        );
+        assert!(
+            entries[i + 1..]
+                .iter()
+                .all(|other| other.code != entry.code),
+            "{} is registered twice",
+            entry.code
+        );
+    }
+}
+
+/// The codes the lexer and parser report can all be explained
+#[test]
+fn reported_codes_are_registered() {
+    for code in [
+        codes::UNCLOSED_QUOTE,
+        codes::INVALID_CHAR_LITERAL,
+        codes::UNRECOGNIZED_CHARACTER,
+        codes::INVALID_ESCAPE,
+        codes::MALFORMED_UNICODE_ESCAPE,
+        codes::INVALID_CODE_POINT,
+        codes::UNCLOSED_INTERPOLATION,
+        codes::MALFORMED_NUMBER,
+        codes::INVALID_DIGIT,
+        codes::INVALID_NUMBER_SUFFIX,
+        codes::NUMBER_OUT_OF_RANGE,
+        codes::MIXED_SCRIPT_IDENTIFIER,
+        codes::CONFUSABLE_IDENTIFIER,
+        codes::MIXED_DIGIT_SYSTEMS,
+        codes::UNCLOSED_BLOCK_COMMENT,
+        codes::EXPECTED_TOKEN,
+        codes::EXPECTED_EXPRESSION,
+        codes::EXPECTED_PATTERN,
+        codes::EXPECTED_TYPE,
+        codes::UNCLOSED_BLOCK,
+        codes::UNCLOSED_MATCH,
+        codes::MISSING_ARM_SEPARATOR,
+        codes::INVALID_ASSIGNMENT,
+        codes::UNATTACHED_DOC_COMMENT,
+    ] {
+        assert_eq!(explain(code).map(|entry| entry.code), Some(code));
+    }
+}
+
+/// Codes are looked up ignoring case and surrounding whitespace, and unknown codes aren't found
+#[test]
+fn codes_are_looked_up_loosely() {
+    assert_eq!(explain(" r0001 ").map(|entry| entry.code), Some("R0001"));
+    assert!(explain("R9999").is_none());
+}
+
+/// The table of codes in the CLI docs lists every registered code, in order, with its title
+#[test]
+fn code_docs_are_up_to_date() {
+    let documented: Vec<(String, String)> = documented_codes();
+    let registered: Vec<&str> = registry().iter().map(|entry| entry.code).collect();
+    let listed: Vec<&str> = documented.iter().map(|(code, _)| code.as_str()).collect();
+    assert_eq!(listed, registered);
+
+    for (code, description) in &documented {
+        let title: &str = explain(code).unwrap().title;
+        assert_eq!(description.replace('`', ""), title);
+    }
+}
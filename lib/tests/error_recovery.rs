@@ -0,0 +1,85 @@
+use rumil_parser::{ast::Ast, diagnostic::Diagnostics, lexer::scan};
+
+/// Utility function to parse source code, returning the items as text along with the messages and lines
+/// of every problem found
+fn parse(source_code: &str) -> (Vec<String>, Vec<(String, i32)>) {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let tokens = scan(source_code, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+    let ast: Box<Ast> = Ast::new(tokens, &mut diagnostics);
+    let items: Vec<String> = ast.items.iter().map(|item| item.to_string()).collect();
+    let problems: Vec<(String, i32)> = diagnostics
+        .iter()
+        .map(|d| (d.message.clone(), d.span.start.line))
+        .collect();
+    (items, problems)
+}
+
+/// Every syntax error is reported, and the statements around them are still parsed
+#[test]
+fn every_error_is_reported() {
+    let (items, problems) = parse("a := )\nb := 2\nc := [1, +]\nd := 4 *\n");
+    assert_eq!(
+        items,
+        [
+            "a := <error>",
+            "b := 2",
+            "c := [1, <error>]",
+            "d := <error>"
+        ]
+    );
+    let lines: Vec<i32> = problems.iter().map(|(_, line)| *line).collect();
+    assert_eq!(lines, [1, 3, 4], "{:?}", problems);
+}
+
+/// An error inside a block skips to the end of its line, and the block and everything after it carry on
+#[test]
+fn blocks_recover_at_line_ends() {
+    let source_code: &str = "f := (x) -> {\n    y := x +* 2\n    -> y\n}\ng := 1\n";
+    let (items, problems) = parse(source_code);
+    assert_eq!(items, ["f := (x) -> { y := <error>, -> y }", "g := 1"]);
+    assert_eq!(problems.len(), 1, "{:?}", problems);
+}
+
+/// An error in a list or match skips to the next comma, or to a closing brace
+#[test]
+fn lists_and_matches_recover_at_commas_and_braces() {
+    let (items, problems) = parse("foo(1, +, 3)\nk := v ? { 1 => ), 2 => b }\nz := (1,\n");
+    assert_eq!(
+        items,
+        [
+            "foo(1, <error>, 3)",
+            "k := (v ? { 1 => <error>, 2 => b })",
+            "z := <error>"
+        ]
+    );
+    assert_eq!(problems.len(), 3, "{:?}", problems);
+
+    let (items, problems) = parse("f := () -> {\n    x := (1,\n}\ny := 2\n");
+    assert_eq!(items, ["f := () -> { x := <error> }", "y := 2"]);
+    assert_eq!(problems.len(), 1, "{:?}", problems);
+}
+
+/// Stray closing brackets are skipped over rather than stalling the parser
+#[test]
+fn stray_closers_are_skipped() {
+    let (items, problems) = parse("}\n)\nx := 1\n");
+    assert_eq!(items, ["<error>", "<error>", "x := 1"]);
+    assert_eq!(problems.len(), 2, "{:?}", problems);
+}
+
+/// Unclosed blocks are reported once, and keep the statements parsed inside them
+#[test]
+fn unclosed_blocks_keep_their_statements() {
+    let (items, problems) = parse("f := (x) -> {\n    y := x\n");
+    assert_eq!(items, ["f := (x) -> { y := x }"]);
+    assert_eq!(problems.len(), 1, "{:?}", problems);
+    assert!(problems[0].0.starts_with("Unclosed block"));
+}
+
+/// Errors the lexer already reported don't cause more syntax errors after them
+#[test]
+fn lexer_errors_are_not_reported_again() {
+    let (items, problems) = parse("x := \"unclosed");
+    assert_eq!(items, ["x := <error>"]);
+    assert_eq!(problems.len(), 1, "{:?}", problems);
+}
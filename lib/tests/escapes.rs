@@ -0,0 +1,78 @@
+use std::borrow::Cow;
+
+use rumil_parser::{
+    diagnostic::Diagnostics,
+    lexer::scan,
+    token::{LiteralValue, Token},
+};
+
+/// Utility function to scan a single literal, returning its token and the codes of any problems found
+fn scan_literal(source_code: &str) -> (Token<'_>, Vec<String>) {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let mut tokens: Vec<Token> =
+        scan(source_code, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+    assert_eq!(tokens.len(), 1);
+    let codes: Vec<String> = diagnostics.iter().filter_map(|d| d.code.clone()).collect();
+    (tokens.remove(0), codes)
+}
+
+/// Utility function to get the decoded contents of a string literal
+fn decoded<'a>(tk: &'a Token) -> &'a Cow<'a, str> {
+    match &tk.literal {
+        Some(LiteralValue::Str(contents)) => contents,
+        _ => panic!("Expected a string literal"),
+    }
+}
+
+/// Every single char escape decodes to the char it stands for
+#[test]
+fn simple_escapes_are_decoded() {
+    let (tk, codes) = scan_literal(r#""a\tb\n\\ \" \' \` \0 \a \b \v \f \r""#);
+    assert!(codes.is_empty());
+    assert_eq!(
+        decoded(&tk),
+        "a\tb\n\\ \" ' ` \0 \u{7} \u{8} \u{b} \u{c} \r"
+    );
+}
+
+/// Unicode escapes take exactly four hex digits, or one to six in braces
+#[test]
+fn unicode_escapes_are_decoded() {
+    let (tk, codes) = scan_literal(r#""é\u{1f600}\u{41}""#);
+    assert!(codes.is_empty());
+    assert_eq!(decoded(&tk), "é😀A");
+
+    let (tk, codes) = scan_literal(r"'\u{1f600}'");
+    assert!(codes.is_empty());
+    assert!(matches!(tk.literal, Some(LiteralValue::Char('😀'))));
+}
+
+/// Strings without escapes borrow their contents from the source code
+#[test]
+fn plain_strings_are_borrowed() {
+    let (tk, _) = scan_literal(r#""plain""#);
+    assert!(matches!(decoded(&tk), Cow::Borrowed("plain")));
+    let (tk, _) = scan_literal(r#""tab\t""#);
+    assert!(matches!(decoded(&tk), Cow::Owned(_)));
+}
+
+/// Bad escapes are reported with a span over just the escape, and decode to a replacement char
+#[test]
+fn bad_escapes_are_reported() {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let tokens: Vec<Token> = scan(r#""a\qb""#, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+    assert_eq!(decoded(&tokens[0]), "a\u{fffd}b");
+    let spans: Vec<String> = diagnostics.iter().map(|d| d.span.to_string()).collect();
+    assert_eq!(spans, ["1:3-1:5"]);
+
+    for (source_code, code) in [
+        (r#""\q""#, "R0004"),
+        (r#""\u00g9""#, "R0005"),
+        (r#""\u{}""#, "R0005"),
+        (r#""\u{d800}""#, "R0006"),
+        (r#""\u{110000}""#, "R0006"),
+    ] {
+        let (_, codes) = scan_literal(source_code);
+        assert_eq!(codes, [code], "{}", source_code);
+    }
+}
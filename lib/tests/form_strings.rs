@@ -0,0 +1,98 @@
+use rumil_parser::{
+    ast::Ast,
+    diagnostic::{Diagnostic, Diagnostics},
+    lexer::scan,
+    token::{FormSegment, LiteralValue, Token, TokenType},
+};
+
+/// Utility function to scan source code, returning its tokens and every problem found
+fn scan_all(source_code: &str) -> (Vec<Token<'_>>, Diagnostics) {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let tokens: Vec<Token> = scan(source_code, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+    (tokens, diagnostics)
+}
+
+/// An unclosed interpolation ends at the form string's closing quote, and is the only problem reported
+#[test]
+fn unclosed_interpolations_stop_at_the_closing_quote() {
+    let (tokens, diagnostics) = scan_all("`a {b c`\nx := 1\n");
+    let problems: Vec<&Diagnostic> = diagnostics.iter().collect();
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0].code.as_deref(), Some("R0007"));
+    assert_eq!(problems[0].span.to_string(), "1:1-1:9");
+
+    assert_eq!(tokens[0].token_type, TokenType::Error);
+    assert_eq!(tokens[0].value, "`a {b c`");
+    let rest: Vec<&str> = tokens[1..].iter().map(|tk| tk.value).collect();
+    assert_eq!(rest, ["x", ":=", "1"]);
+}
+
+/// Utility function to describe the segments of a single form string, as text or the tokens of code
+fn segments(source_code: &str) -> Vec<String> {
+    let (tokens, diagnostics) = scan_all(source_code);
+    assert!(diagnostics.is_empty());
+    let Some(LiteralValue::Form(segments)) = &tokens[0].literal else {
+        panic!("Expected a form string");
+    };
+    segments
+        .iter()
+        .map(|segment| match segment {
+            FormSegment::Text(text, span) => format!("{:?} {}", text, span),
+            FormSegment::Code(tokens, span) => {
+                let values: Vec<&str> = tokens.iter().map(|tk| tk.value).collect();
+                format!("{} {}", values.join(" "), span)
+            }
+        })
+        .collect()
+}
+
+/// Form strings split into decoded text and the tokens of each embedded expression, braces included
+#[test]
+fn form_strings_split_into_segments() {
+    assert_eq!(
+        segments("`Hi {name}, you have {count + 1}\\n`"),
+        [
+            "\"Hi \" 1:2-1:5",
+            "{ name } 1:5-1:11",
+            "\", you have \" 1:11-1:22",
+            "{ count + 1 } 1:22-1:33",
+            "\"\\n\" 1:33-1:35",
+        ]
+    );
+    assert_eq!(segments("`{a}{b}`"), ["{ a } 1:2-1:5", "{ b } 1:5-1:8"]);
+    assert_eq!(segments("`plain`"), ["\"plain\" 1:2-1:7"]);
+}
+
+/// Braces, quotes and form strings inside an embedded expression don't end it early
+#[test]
+fn embedded_expressions_nest() {
+    assert_eq!(
+        segments("`a { {1, 2} } b`"),
+        [
+            "\"a \" 1:2-1:4",
+            "{ { 1 , 2 } } 1:4-1:14",
+            "\" b\" 1:14-1:16"
+        ]
+    );
+    assert_eq!(
+        segments("`x {\"}\"} y`"),
+        ["\"x \" 1:2-1:4", "{ \"}\" } 1:4-1:9", "\" y\" 1:9-1:11"]
+    );
+    assert_eq!(
+        segments("`a {f(`in {y}`)} b`"),
+        [
+            "\"a \" 1:2-1:4",
+            "{ f ( `in {y}` ) } 1:4-1:17",
+            "\" b\" 1:17-1:19"
+        ]
+    );
+}
+
+/// The parser turns the segments into text and expressions
+#[test]
+fn segments_are_parsed() {
+    let (tokens, mut diagnostics) = scan_all("x := `Hi {name}, {count + 1}!`\n");
+    let ast: Box<Ast> = Ast::new(tokens, &mut diagnostics);
+    assert!(diagnostics.is_empty());
+    assert_eq!(ast.items[0].to_string(), "x := `Hi {name}, {(count + 1)}!`");
+}
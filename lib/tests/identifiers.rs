@@ -0,0 +1,70 @@
+use rumil_parser::{
+    ast::Ast,
+    diagnostic::{Diagnostic, Diagnostics, Severity},
+    lexer::scan,
+    token::{Token, TokenType},
+};
+
+/// Utility function to scan source code, returning its tokens and every problem found
+fn scan_all(source_code: &str) -> (Vec<Token<'_>>, Diagnostics) {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let tokens: Vec<Token> = scan(source_code, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+    (tokens, diagnostics)
+}
+
+/// Identifiers start with any XID_Start char or an underscore, and go on with XID_Continue chars
+/// such as combining marks
+#[test]
+fn identifiers_follow_uax_31() {
+    let (tokens, diagnostics) = scan_all("αβγ := 1\nимя := 2\n名前 := 3\n_ü := 4\nx\u{301} := 5\n");
+    assert!(diagnostics.is_empty());
+    let names: Vec<&str> = tokens
+        .iter()
+        .filter(|tk| tk.token_type == TokenType::Identifier)
+        .map(|tk| tk.value)
+        .collect();
+    assert_eq!(names, ["αβγ", "имя", "名前", "_ü", "x\u{301}"]);
+}
+
+/// Precomposed and decomposed spellings are the same name, compared in NFC
+#[test]
+fn names_are_normalized() {
+    let (tokens, mut diagnostics) = scan_all("café := 1\nx := cafe\u{301}\n");
+    assert_eq!(tokens[5].value, "cafe\u{301}");
+    assert_eq!(tokens[5].name(), "café");
+
+    let ast: Box<Ast> = Ast::new(tokens, &mut diagnostics);
+    assert!(diagnostics.is_empty());
+    assert_eq!(ast.items[1].to_string(), "x := café");
+}
+
+/// Identifiers that mix scripts are warned about, but still scanned
+#[test]
+fn mixed_scripts_are_warned_about() {
+    let (tokens, diagnostics) = scan_all("pаth := 7\n");
+    assert_eq!(tokens[0].token_type, TokenType::Identifier);
+
+    let problems: Vec<&Diagnostic> = diagnostics.iter().collect();
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0].severity, Severity::Warning);
+    assert_eq!(problems[0].code.as_deref(), Some("R0012"));
+    assert_eq!(problems[0].span.to_string(), "1:1-1:5");
+}
+
+/// An identifier that looks like an earlier, different one is warned about, pointing at both
+#[test]
+fn confusable_identifiers_are_warned_about() {
+    let (_, diagnostics) = scan_all("scope := 1\nѕcope := 2\n");
+    let confusable: &Diagnostic = diagnostics
+        .iter()
+        .find(|d| d.code.as_deref() == Some("R0013"))
+        .unwrap();
+    assert_eq!(confusable.severity, Severity::Warning);
+    assert_eq!(
+        confusable.message,
+        "Identifier [ѕcope] can be confused with [scope]"
+    );
+    assert_eq!(confusable.span.to_string(), "2:1-2:6");
+    assert_eq!(confusable.labels[0].span.to_string(), "1:1-1:6");
+    assert!(!diagnostics.has_errors());
+}
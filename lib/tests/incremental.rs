@@ -0,0 +1,170 @@
+use std::{ops::Range, sync::Arc};
+
+use rumil_parser::{
+    ast::{ItemKind, StmtKind},
+    syntax::{GreenNode, SyntaxNode, SyntaxTree, TextEdit},
+};
+
+/// Source code with nested blocks, comments and a function after the one being edited
+const SOURCE: &str = "\
+;; Counts to ten
+count := () -> {
+    i := 0
+    @ i < 10 {
+        $(i) ; show it
+        i += 1
+    }
+}
+
+;; Doubles a number
+double := (n: int) -> int { -> n * 2 }
+";
+
+/// Utility function to check that an edited tree is the same as one parsed from scratch
+fn assert_same_as_full_parse(tree: &SyntaxTree) {
+    let full: SyntaxTree = SyntaxTree::parse(&tree.text());
+    assert_eq!(tree.root().dump(), full.root().dump());
+
+    let items = |tree: &SyntaxTree| -> Vec<String> {
+        tree.ast()
+            .items
+            .iter()
+            .map(|item| format!("{} {} {:?}", item, item.span, item.docs))
+            .collect()
+    };
+    assert_eq!(items(tree), items(&full));
+
+    let diagnostics = |tree: &SyntaxTree| -> Vec<String> {
+        tree.diagnostics()
+            .iter()
+            .map(|d| format!("{} {}", d.message, d.span))
+            .collect()
+    };
+    assert_eq!(diagnostics(tree), diagnostics(&full));
+}
+
+/// Utility function to get the green node of an item
+fn item(tree: &SyntaxTree, index: usize) -> Arc<GreenNode> {
+    tree.root().children().nth(index).unwrap().green().clone()
+}
+
+/// Typing inside a block reparses just that block, and leaves every other node as it was
+#[test]
+fn edits_reparse_the_smallest_block() {
+    let mut tree: SyntaxTree = SyntaxTree::parse(SOURCE);
+    let at: usize = SOURCE.find("i += 1").unwrap();
+    for (i, c) in "$(i * 2)\n        ".chars().enumerate() {
+        tree.edit(&TextEdit::new(at + i..at + i, &c.to_string()))
+            .unwrap();
+        assert_same_as_full_parse(&tree);
+    }
+    assert_eq!(
+        tree.text(),
+        SOURCE.replace("i += 1", "$(i * 2)\n        i += 1")
+    );
+
+    let double: Arc<GreenNode> = item(&tree, 1);
+    let at: usize = tree.text().find("i * 2").unwrap();
+    let reparsed: Range<usize> = tree.edit(&TextEdit::new(at..at + 5, "i + i")).unwrap();
+    assert_same_as_full_parse(&tree);
+
+    let text: String = tree.text();
+    assert_eq!(
+        &text[reparsed],
+        "{\n        $(i) ; show it\n        $(i + i)\n        i += 1\n    }"
+    );
+    assert!(Arc::ptr_eq(&double, &item(&tree, 1)));
+
+    // The Ast after the first edit has moved down a line
+    let ItemKind::Function { function, .. } = &tree.ast().items[0].kind else {
+        panic!("Expected a function");
+    };
+    let StmtKind::Expr(outer) = &function.body.statements[1].kind else {
+        panic!("Expected a loop");
+    };
+    assert_eq!(tree.syntax(outer.span).text(), &text[outer.span.range()]);
+    assert_eq!(tree.ast().items[1].span.start.line, 12);
+}
+
+/// Edits that can't be parsed inside one block parse the whole file again, giving the same tree
+#[test]
+fn edits_that_change_nesting_parse_everything() {
+    let mut tree: SyntaxTree = SyntaxTree::parse(SOURCE);
+    let edits: [(&str, &str); 4] = [
+        ("i < 10 {", "i < 10 { }"),
+        ("$(i)", "$("),
+        ("i := 0", "i := \"0"),
+        ("count", "counter"),
+    ];
+    for (old, new) in edits {
+        let at: usize = tree.text().find(old).unwrap();
+        let reparsed: Range<usize> = tree.edit(&TextEdit::new(at..at + old.len(), new)).unwrap();
+        assert_eq!(reparsed, 0..tree.text().len());
+        assert_same_as_full_parse(&tree);
+    }
+    assert!(tree.diagnostics().has_errors());
+}
+
+/// Syntax errors inside a reparsed block come and go with the edits, while the ones outside it stay
+#[test]
+fn diagnostics_follow_edits() {
+    let source: String = format!("{}bad := )\n", SOURCE);
+    let mut tree: SyntaxTree = SyntaxTree::parse(&source);
+    assert_eq!(tree.diagnostics().len(), 1);
+
+    let at: usize = source.find("i += 1").unwrap() + "i += ".len();
+    tree.edit(&TextEdit::new(at..at + 1, "\n")).unwrap();
+    let lines: Vec<i32> = tree
+        .diagnostics()
+        .iter()
+        .map(|d| d.span.start.line)
+        .collect();
+    assert_eq!(lines, [8, 13]);
+    assert_same_as_full_parse(&tree);
+
+    tree.edit(&TextEdit::new(at..at + 1, "2")).unwrap();
+    let lines: Vec<i32> = tree
+        .diagnostics()
+        .iter()
+        .map(|d| d.span.start.line)
+        .collect();
+    assert_eq!(lines, [12]);
+    assert_same_as_full_parse(&tree);
+}
+
+/// An edit has to fit in the source code
+#[test]
+fn edits_outside_the_source_code_are_rejected() {
+    let mut tree: SyntaxTree = SyntaxTree::parse("x := 1\n");
+    let root: SyntaxNode = tree.root();
+    assert_eq!(
+        tree.edit(&TextEdit::new(5..9, "2")).unwrap_err(),
+        "Edit range [5..9] isn't in the source code"
+    );
+    assert!(Arc::ptr_eq(root.green(), tree.root().green()));
+}
+
+/// A form string whose interpolation read ahead to find a closing quote is scanned again when an edit
+/// changes what it read, even though the edit is past the end of its token
+#[test]
+fn edits_rescan_tokens_that_read_past_their_end() {
+    let mut tree: SyntaxTree = SyntaxTree::parse("{()\"\"`{` *;\n}");
+    tree.edit(&TextEdit::new(11..11, "`")).unwrap();
+    assert_same_as_full_parse(&tree);
+    let messages: Vec<String> = tree
+        .diagnostics()
+        .iter()
+        .map(|d| format!("{} {}", d.message, d.span))
+        .collect();
+    assert_eq!(messages, ["Unclosed quote [`] 1:6-2:2"]);
+
+    // Inside a function, the block holding the form string is parsed again on its own
+    let source: &str = "f := () -> {\n    $(`{`)\n    x := 1 ; note\n}\n";
+    let mut tree: SyntaxTree = SyntaxTree::parse(source);
+    let at: usize = source.find("; note").unwrap();
+    for (i, c) in "` {".chars().enumerate() {
+        tree.edit(&TextEdit::new(at + i..at + i, &c.to_string()))
+            .unwrap();
+        assert_same_as_full_parse(&tree);
+    }
+}
@@ -0,0 +1,55 @@
+use rumil_parser::{
+    ast::Ast,
+    diagnostic::{Diagnostic, Diagnostics},
+    lexer::scan,
+};
+use serde_json::{Value, json};
+
+/// Utility function to scan and parse source code, writing every problem found as JSON
+fn json_all(source_code: &str) -> Vec<String> {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let tokens = scan(source_code, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+    Ast::new(tokens, &mut diagnostics);
+    diagnostics.iter().map(|d| d.to_json("test.rum")).collect()
+}
+
+/// Each diagnostic is one line of JSON with every field of the documented format
+#[test]
+fn diagnostics_are_written_as_json() {
+    let lines: Vec<String> = json_all("x := \"é\n");
+    assert_eq!(lines.len(), 1);
+    assert!(!lines[0].contains('\n'));
+
+    let value: Value = serde_json::from_str(&lines[0]).unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "file": "test.rum",
+            "severity": "error",
+            "code": "R0001",
+            "message": "Unclosed quote [\"]",
+            "span": {
+                "start": { "line": 1, "col": 6, "offset": 5 },
+                "end": { "line": 2, "col": 1, "offset": 9 }
+            },
+            "labels": [],
+            "notes": [],
+            "help": "Add a closing [\"]"
+        })
+    );
+}
+
+/// Labels keep their spans and messages, and missing codes and help are null
+#[test]
+fn labels_and_missing_fields_are_written() {
+    let lines: Vec<String> = json_all("f := () -> {\n");
+    let value: Value = serde_json::from_str(&lines[0]).unwrap();
+    assert_eq!(value["code"], "R0105");
+    assert_eq!(value["labels"][0]["message"], "Block opened here");
+    assert_eq!(value["labels"][0]["span"]["start"]["col"], 12);
+
+    let bare: Diagnostic = Diagnostic::warning("Odd".to_owned(), Default::default());
+    let value: Value = serde_json::from_str(&bare.to_json("a.rum")).unwrap();
+    assert_eq!(value["severity"], "warning");
+    assert!(value["code"].is_null() && value["help"].is_null());
+}
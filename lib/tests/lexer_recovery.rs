@@ -0,0 +1,68 @@
+use rumil_parser::{
+    diagnostic::Diagnostics,
+    lexer::scan,
+    token::{Token, TokenType},
+};
+
+/// Utility function to scan source code, returning its tokens and every problem found
+fn scan_all(source_code: &str) -> (Vec<Token<'_>>, Diagnostics) {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let tokens: Vec<Token> = scan(source_code, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+    (tokens, diagnostics)
+}
+
+/// Source code that can't be scanned becomes an error token, and scanning carries on after it
+#[test]
+fn errors_become_tokens() {
+    let (tokens, diagnostics) = scan_all("price := 1 £ 2\nx := 'ab'\ny := \"open\n");
+    let scanned: Vec<(&str, TokenType)> =
+        tokens.iter().map(|tk| (tk.value, tk.token_type)).collect();
+    assert!(
+        scanned
+            == [
+                ("price", TokenType::Identifier),
+                (":=", TokenType::ColonEquals),
+                ("1", TokenType::Int),
+                ("£", TokenType::Error),
+                ("2", TokenType::Int),
+                ("x", TokenType::Identifier),
+                (":=", TokenType::ColonEquals),
+                ("'ab'", TokenType::Error),
+                ("y", TokenType::Identifier),
+                (":=", TokenType::ColonEquals),
+                ("\"open\n", TokenType::Error),
+            ]
+    );
+    assert_eq!(diagnostics.len(), 3);
+}
+
+/// Each error token carries its message, and is reported once with the same message and span
+#[test]
+fn error_tokens_match_their_diagnostics() {
+    let (tokens, diagnostics) = scan_all("a := 1 §§ b\n");
+    let errors: Vec<(&str, String)> = tokens
+        .iter()
+        .filter(|tk| tk.token_type == TokenType::Error)
+        .map(|tk| (tk.message.as_deref().unwrap(), tk.span.to_string()))
+        .collect();
+    let reported: Vec<(&str, String)> = diagnostics
+        .iter()
+        .map(|d| (d.message.as_str(), d.span.to_string()))
+        .collect();
+    assert_eq!(errors, reported);
+    let spans: Vec<&str> = errors.iter().map(|(_, span)| span.as_str()).collect();
+    assert_eq!(spans, ["1:8-1:9", "1:9-1:10"]);
+    assert_eq!(errors[0].0, "Unrecognized character [§]");
+    assert_eq!(tokens.last().unwrap().value, "b");
+}
+
+/// Only a file with no tokens at all fails to scan
+#[test]
+fn empty_files_fail_to_scan() {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    assert_eq!(
+        scan("", &"test.rum".to_owned(), &mut diagnostics).err(),
+        Some("No source code found in test.rum".to_owned())
+    );
+    assert!(scan("; a comment\n", &"test.rum".to_owned(), &mut diagnostics).is_ok());
+}
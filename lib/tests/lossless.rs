@@ -0,0 +1,105 @@
+use rumil_parser::{
+    diagnostic::Diagnostics,
+    lexer::{scan, scan_lossless, source_text},
+    token::{Token, TokenType, Trivia, TriviaKind},
+};
+
+/// Utility function to scan source code losslessly
+fn scan_all(source_code: &str) -> Vec<Token<'_>> {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    scan_lossless(source_code, &mut diagnostics)
+}
+
+/// Utility function to describe a list of trivia as its kinds and text
+fn describe(trivia: &[Trivia]) -> Vec<String> {
+    trivia
+        .iter()
+        .map(|t| format!("{} {:?}", t.kind, t.text))
+        .collect()
+}
+
+/// Joining the tokens with their trivia gives back the source code byte for byte, whatever is in it
+#[test]
+fn scanning_is_lossless() {
+    for source_code in [
+        "x := 1 ; one\n\n; two\ny\n",
+        "\t;; docs\r\nf := () -> {  -> `a {b}`  }\r\n",
+        ";* block ;* nested *; *;\nbad := \"open\n",
+        "a £ b   ",
+        "",
+        "  \n\n",
+    ] {
+        let tokens: Vec<Token> = scan_all(source_code);
+        assert_eq!(source_text(&tokens), source_code);
+        assert_eq!(tokens.last().unwrap().token_type, TokenType::EOF);
+    }
+}
+
+/// Trivia up to the end of a token's line trails it, and the rest leads the next token
+#[test]
+fn trivia_is_split_at_line_ends() {
+    let tokens: Vec<Token> = scan_all("x := 1 ; one\n\n; two\ny\n");
+    assert_eq!(
+        describe(&tokens[2].trailing),
+        ["Whitespace \" \"", "Comment \"; one\"", "Newline \"\\n\""]
+    );
+    assert_eq!(
+        describe(&tokens[3].leading),
+        ["Newline \"\\n\"", "Comment \"; two\"", "Newline \"\\n\""]
+    );
+    assert_eq!(tokens[3].value, "y");
+    assert_eq!(tokens[3].full_text(), "\n; two\ny\n");
+}
+
+/// Comments of every kind are trivia rather than tokens, and carriage returns stay with their line break
+#[test]
+fn comments_are_trivia() {
+    let tokens: Vec<Token> = scan_all(";; docs\r\n;* block *; x ; done");
+    let values: Vec<&str> = tokens.iter().map(|tk| tk.value).collect();
+    assert_eq!(values, ["x", ""]);
+    assert_eq!(
+        describe(&tokens[0].leading),
+        [
+            "DocComment \";; docs\"",
+            "Newline \"\\r\\n\"",
+            "BlockComment \";* block *;\"",
+            "Whitespace \" \""
+        ]
+    );
+    assert_eq!(tokens[0].leading[2].kind, TriviaKind::BlockComment);
+    assert_eq!(tokens[0].leading[2].span.to_string(), "2:1-2:12");
+}
+
+/// Trivia after the last token is held by the EOF token
+#[test]
+fn trailing_trivia_goes_to_eof() {
+    let tokens: Vec<Token> = scan_all("x\n\n; end\n");
+    let eof: &Token = tokens.last().unwrap();
+    assert_eq!(
+        describe(&eof.leading),
+        ["Newline \"\\n\"", "Comment \"; end\"", "Newline \"\\n\""]
+    );
+}
+
+/// Scanning without trivia gives the same tokens, just without their trivia or the EOF token
+#[test]
+fn plain_scans_have_no_trivia() {
+    let source_code: &str = "x := 1 ; one\ny := `a {b}`\n";
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let plain: Vec<Token> = scan(source_code, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+    let lossless: Vec<Token> = scan_all(source_code);
+
+    assert!(
+        plain
+            .iter()
+            .all(|tk| tk.leading.is_empty() && tk.trailing.is_empty())
+    );
+    let values = |tokens: &[Token]| -> Vec<String> {
+        tokens
+            .iter()
+            .filter(|tk| !matches!(tk.token_type, TokenType::Comment | TokenType::EOF))
+            .map(|tk| format!("{} {}", tk.value, tk.span))
+            .collect()
+    };
+    assert_eq!(values(&plain), values(&lossless));
+}
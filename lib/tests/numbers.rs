@@ -0,0 +1,122 @@
+use rumil_parser::{
+    diagnostic::Diagnostics,
+    lexer::scan,
+    token::{LiteralValue, Token, TokenType},
+};
+
+/// Utility function to scan number literals that have no problems, describing each one's value
+fn values(source_code: &str) -> Vec<String> {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let tokens: Vec<Token> = scan(source_code, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+    assert!(diagnostics.is_empty());
+    tokens
+        .iter()
+        .map(|tk| {
+            let suffix = |suffix: &Option<_>| suffix.map_or(String::new(), |s| format!(" {}", s));
+            match &tk.literal {
+                Some(LiteralValue::Int(value, s)) => {
+                    format!("{} {}{}", tk.token_type, value, suffix(s))
+                }
+                Some(LiteralValue::Float(value, s)) => {
+                    format!("{} {}{}", tk.token_type, value, suffix(s))
+                }
+                _ => format!("{} {}", tk.token_type, tk.value),
+            }
+        })
+        .collect()
+}
+
+/// Hexadecimal, octal and binary literals take a prefix in either case
+#[test]
+fn prefixed_integers_are_read() {
+    assert_eq!(
+        values("0xff 0XFF 0o755 0b1010"),
+        ["Int 255", "Int 255", "Int 493", "Int 10"]
+    );
+}
+
+/// Underscores separate digits anywhere in a literal, and don't change its value
+#[test]
+fn separators_are_skipped() {
+    assert_eq!(
+        values("1_000_000 0xff_ff 0b1010_1010 1_0.2_5"),
+        ["Int 1000000", "Int 65535", "Int 170", "Float 10.25"]
+    );
+}
+
+/// A fraction or an exponent makes a float, and exponents can have a sign
+#[test]
+fn fractions_and_exponents_make_floats() {
+    assert_eq!(
+        values("1.5 1.5e-3 2E10 1e+2"),
+        [
+            "Float 1.5",
+            "Float 0.0015",
+            "Float 20000000000",
+            "Float 100"
+        ]
+    );
+
+    // A dot not followed by a digit isn't part of the number
+    assert_eq!(values("1..2"), ["Int 1", "Dot .", "Dot .", "Int 2"]);
+}
+
+/// Type suffixes are kept with the value, and a float suffix makes a float
+#[test]
+fn suffixes_are_read() {
+    assert_eq!(
+        values("255u8 128i8 1.5f32 3f64"),
+        ["Int 255 u8", "Int 128 i8", "Float 1.5 f32", "Float 3 f64"]
+    );
+
+    // f32 is made of hex digits, so it's part of a hexadecimal literal
+    assert_eq!(values("0xfff32"), ["Int 1048370"]);
+}
+
+/// Malformed and out of range literals become error tokens, reported with a span over the problem
+#[test]
+fn bad_literals_are_errors() {
+    for (source_code, code, span) in [
+        ("0x", "R0008", "1:1-1:3"),
+        ("1.5e", "R0008", "1:4-1:5"),
+        ("0b102", "R0009", "1:5-1:6"),
+        ("255byte", "R0010", "1:4-1:8"),
+        ("0.5i32", "R0010", "1:4-1:7"),
+        ("1abc", "R0010", "1:2-1:5"),
+        ("256u8", "R0011", "1:1-1:6"),
+        ("129i8", "R0011", "1:1-1:6"),
+        ("1e400", "R0011", "1:1-1:6"),
+        ("3.4e39f32", "R0011", "1:1-1:10"),
+        (
+            "340282366920938463463374607431768211456",
+            "R0011",
+            "1:1-1:40",
+        ),
+    ] {
+        let mut diagnostics: Diagnostics = Diagnostics::new();
+        let tokens: Vec<Token> =
+            scan(source_code, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+        assert_eq!(tokens.len(), 1, "{}", source_code);
+        assert_eq!(tokens[0].token_type, TokenType::Error, "{}", source_code);
+        assert_eq!(tokens[0].value, source_code);
+
+        let problems: Vec<(&str, String)> = diagnostics
+            .iter()
+            .map(|d| (d.code.as_deref().unwrap(), d.span.to_string()))
+            .collect();
+        assert_eq!(problems, [(code, span.to_owned())], "{}", source_code);
+    }
+}
+
+/// The largest values of each type are still in range
+#[test]
+fn limits_are_in_range() {
+    assert_eq!(
+        values("255u8 128i8 340282366920938463463374607431768211455"),
+        [
+            "Int 255 u8",
+            "Int 128 i8",
+            "Int 340282366920938463463374607431768211455"
+        ]
+    );
+}
@@ -0,0 +1,90 @@
+use rumil_parser::{
+    diagnostic::{Diagnostic, Diagnostics},
+    lexer::scan,
+    token::{LiteralValue, Token, TokenType},
+};
+
+/// Utility function to scan source code, returning its tokens and every problem found
+fn scan_all(source_code: &str) -> (Vec<Token<'_>>, Diagnostics) {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let tokens: Vec<Token> = scan(source_code, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+    (tokens, diagnostics)
+}
+
+/// Decimal literals can be written in the digits of any script, and have the same values as in ASCII
+#[test]
+fn localized_digits_are_read() {
+    let (tokens, diagnostics) = scan_all("١٢٣ ۴۵ १२.५ ๓e๒ ௧௨u8 ١_٠٠٠");
+    assert!(diagnostics.is_empty());
+    let values: Vec<String> = tokens
+        .iter()
+        .map(|tk| match &tk.literal {
+            Some(LiteralValue::Int(value, None)) => format!("Int {}", value),
+            Some(LiteralValue::Int(value, Some(suffix))) => format!("Int {} {}", value, suffix),
+            Some(LiteralValue::Float(value, _)) => format!("Float {}", value),
+            _ => panic!("Expected a number"),
+        })
+        .collect();
+    assert_eq!(
+        values,
+        [
+            "Int 123",
+            "Int 45",
+            "Float 12.5",
+            "Float 300",
+            "Int 12 u8",
+            "Int 1000"
+        ]
+    );
+
+    // The token keeps the digits as written
+    assert_eq!(tokens[0].value, "١٢٣");
+}
+
+/// A literal can't mix digits from different scripts, and the problem points at the first digit that differs
+#[test]
+fn mixed_digit_systems_are_errors() {
+    for (source_code, message, span, label) in [
+        (
+            "12३",
+            "Number literal [12३] mixes Western and Devanagari digits",
+            "1:3-1:4",
+            "1:1-1:2",
+        ),
+        (
+            "१२.5",
+            "Number literal [१२.5] mixes Devanagari and Western digits",
+            "1:4-1:5",
+            "1:1-1:2",
+        ),
+    ] {
+        let (tokens, diagnostics) = scan_all(source_code);
+        assert_eq!(tokens[0].token_type, TokenType::Error);
+
+        let problems: Vec<&Diagnostic> = diagnostics.iter().collect();
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].code.as_deref(), Some("R0014"));
+        assert_eq!(problems[0].message, message);
+        assert_eq!(problems[0].span.to_string(), span);
+        assert_eq!(problems[0].labels[0].span.to_string(), label);
+    }
+}
+
+/// Only ASCII digits can be used in hexadecimal, octal and binary literals, and localized literals have
+/// the same range limits
+#[test]
+fn localized_digits_keep_the_rules() {
+    let (_, diagnostics) = scan_all("0x१२");
+    let codes: Vec<&str> = diagnostics
+        .iter()
+        .filter_map(|d| d.code.as_deref())
+        .collect();
+    assert_eq!(codes, ["R0009"]);
+
+    let (_, diagnostics) = scan_all("٣٠٠u8");
+    let codes: Vec<&str> = diagnostics
+        .iter()
+        .filter_map(|d| d.code.as_deref())
+        .collect();
+    assert_eq!(codes, ["R0011"]);
+}
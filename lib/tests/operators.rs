@@ -0,0 +1,143 @@
+use std::{env, fs, path::PathBuf};
+
+use rumil_parser::{
+    lexer::Lexer,
+    operators::{OPERATORS, Operators, markdown_reference},
+    token::{Token, TokenType},
+};
+
+/// Utility function to scan source code with the given operators
+fn scan_with<'a>(source_code: &'a str, operators: &'a Operators) -> Vec<Token<'a>> {
+    Lexer::new(source_code).with_operators(operators).collect()
+}
+
+/// The generated operator reference has to match the operator table. Set RUMIL_UPDATE_DOCS to regenerate it
+#[test]
+fn operator_docs_are_up_to_date() {
+    let path: PathBuf =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../docs/syntax/operators.md");
+    let generated: String = markdown_reference();
+    if env::var_os("RUMIL_UPDATE_DOCS").is_some() {
+        fs::write(&path, &generated).expect("couldn't write the operator docs");
+        return;
+    }
+
+    let current: String = fs::read_to_string(&path).unwrap_or_default();
+    assert_eq!(
+        current, generated,
+        "docs/syntax/operators.md is out of date, regenerate it with RUMIL_UPDATE_DOCS=1 cargo test"
+    );
+}
+
+/// No two operators in the table can be spelled the same way
+#[test]
+fn operator_symbols_are_unique() {
+    for (i, operator) in OPERATORS.iter().enumerate() {
+        for other in &OPERATORS[i + 1..] {
+            assert_ne!(
+                operator.symbol, other.symbol,
+                "[{}] is listed twice",
+                operator.symbol
+            );
+        }
+    }
+}
+
+/// Every operator scans on its own as a single token of its type
+#[test]
+fn every_operator_scans_as_itself() {
+    for operator in OPERATORS {
+        let tokens: Vec<Token> = Lexer::new(operator.symbol).collect();
+        assert_eq!(
+            tokens.len(),
+            1,
+            "[{}] scanned as {} tokens",
+            operator.symbol,
+            tokens.len()
+        );
+        assert_eq!(
+            (tokens[0].token_type, tokens[0].value),
+            (operator.token_type, operator.symbol),
+            "[{}] scanned as another token",
+            operator.symbol
+        );
+    }
+}
+
+/// Any two operators written together scan with maximal munch: the first token is always the longest
+/// operator the text starts with, and no text is lost
+#[test]
+fn adjacent_operators_scan_with_maximal_munch() {
+    for first in OPERATORS {
+        for second in OPERATORS {
+            // Two underscores make an identifier, not two operators
+            if first.symbol == "_" && second.symbol == "_" {
+                continue;
+            }
+
+            let source_code: String = format!("{}{}", first.symbol, second.symbol);
+            let longest = OPERATORS
+                .iter()
+                .filter(|operator| source_code.starts_with(operator.symbol))
+                .max_by_key(|operator| operator.symbol.len())
+                .unwrap();
+
+            let tokens: Vec<Token> = Lexer::new(&source_code).collect();
+            assert_eq!(
+                (tokens[0].token_type, tokens[0].value),
+                (longest.token_type, longest.symbol),
+                "[{}] didn't start with its longest operator",
+                source_code
+            );
+            let text: String = tokens.iter().map(|token| token.value).collect();
+            assert_eq!(text, source_code);
+        }
+    }
+}
+
+/// Registered operators scan like standard ones, and take part in maximal munch
+#[test]
+fn registered_operators_scan() {
+    let mut operators: Operators = Operators::standard();
+    operators.register("≠", TokenType::BangEquals).unwrap();
+    operators.register("|>", TokenType::RightArrow).unwrap();
+    operators.register("<=>", TokenType::EqualsEquals).unwrap();
+
+    let tokens: Vec<Token> = scan_with("a ≠ b |> c <=> d <= e", &operators);
+    let types: Vec<TokenType> = tokens.iter().map(|token| token.token_type).collect();
+    assert_eq!(
+        types,
+        [
+            TokenType::Identifier,
+            TokenType::BangEquals,
+            TokenType::Identifier,
+            TokenType::RightArrow,
+            TokenType::Identifier,
+            TokenType::EqualsEquals,
+            TokenType::Identifier,
+            TokenType::LessOrEquals,
+            TokenType::Identifier,
+        ]
+    );
+    assert_eq!(tokens[1].value, "≠");
+    assert_eq!(tokens[5].value, "<=>");
+
+    // The standard operators are untouched
+    let tokens: Vec<Token> = Lexer::new("a |> b").collect();
+    assert_eq!(tokens[1].token_type, TokenType::Pipe);
+}
+
+/// Operators that would clash with other tokens or existing operators are rejected
+#[test]
+fn invalid_operators_are_rejected() {
+    let mut operators: Operators = Operators::standard();
+    for symbol in ["", "and", "_x", "1+", "\"", "'", "`", ";;", "< >", "→2 "] {
+        assert!(
+            operators.register(symbol, TokenType::AndAnd).is_err(),
+            "[{}] was registered",
+            symbol
+        );
+    }
+    assert!(operators.register("==", TokenType::Equals).is_err());
+    assert!(operators.register("==", TokenType::EqualsEquals).is_ok());
+}
@@ -0,0 +1,145 @@
+use rumil_parser::{
+    ast::{Ast, ItemKind},
+    diagnostic::{Diagnostic, Diagnostics},
+    lexer::scan,
+};
+
+/// Utility function to parse source code that has no syntax errors in it
+fn parse(source_code: &str) -> Box<Ast> {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let tokens = scan(source_code, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+    let ast: Box<Ast> = Ast::new(tokens, &mut diagnostics);
+    let problems: Vec<&Diagnostic> = diagnostics.iter().collect();
+    assert!(problems.is_empty(), "{}", problems[0].message);
+    ast
+}
+
+/// Utility function to parse source code and print each item, with its expressions fully parenthesized
+fn items(source_code: &str) -> Vec<String> {
+    parse(source_code)
+        .items
+        .iter()
+        .map(|item| item.to_string())
+        .collect()
+}
+
+/// Binary operators bind by precedence, and operators of the same precedence group to the left
+#[test]
+fn binary_operators_follow_precedence() {
+    assert_eq!(
+        items("x := 1 + 2 * 3 - 4\ny := a || b && c == d ..? e\nz := 1 << 2 >> 3 & 4 ^ 5 | 6\n"),
+        [
+            "x := ((1 + (2 * 3)) - 4)",
+            "y := ((a || (b && (c == d))) ..? e)",
+            "z := (((((1 << 2) >> 3) & 4) ^ 5) | 6)",
+        ]
+    );
+}
+
+/// Prefix operators bind tighter than binary ones, and postfix calls, indexes, fields and paths
+/// bind tighter still
+#[test]
+fn unary_and_postfix_operators_bind_tightly() {
+    assert_eq!(
+        items("w := !a ~= ~b\nn := #xs * 2\ny := -a.b[0](1, 2)::c\n"),
+        [
+            "w := ((!a) ~= (~b))",
+            "n := ((#xs) * 2)",
+            "y := (-a.b[0](1, 2)::c)",
+        ]
+    );
+}
+
+/// Operators, calls and indexes carry on an expression only on the same line, so one at the start
+/// of a line starts a new statement
+#[test]
+fn new_lines_end_expressions() {
+    assert_eq!(
+        items("x := 1\n-y\nf\n(b)\n[c]\n"),
+        ["x := 1", "(-y)", "f", "b", "[c]"]
+    );
+}
+
+/// A function literal bound to a plain name becomes a named function, with its parameter and
+/// return types
+#[test]
+fn functions_become_named_items() {
+    let ast: Box<Ast> = parse(
+        "add := (a: int, b: int) -> int { -> a + b }\n\
+         f := (xs: [?int], g: (int) -> bool) -> (int, str) { -> (1, \"a\") }\n\
+         (a, b) := (1, 2)\n",
+    );
+    let names: Vec<&str> = ast
+        .items
+        .iter()
+        .filter_map(|item| match &item.kind {
+            ItemKind::Function { name, .. } => Some(name.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(names, ["add", "f"]);
+    assert!(matches!(ast.items[2].kind, ItemKind::Binding(_)));
+    assert_eq!(
+        ast.items[1].to_string(),
+        "f := (xs: [?int], g: (int) -> bool) -> (int, str) { -> (1, \"a\") }"
+    );
+}
+
+/// Match arms can use literal, negative, tuple, array and wildcard patterns
+#[test]
+fn match_arms_take_patterns() {
+    assert_eq!(
+        items(
+            "r := v ? { 0 => \"zero\", -1 => \"neg\", (a, _) => a, [x, y] => x, _ => \"other\" }\n"
+        ),
+        ["r := (v ? { 0 => \"zero\", -1 => \"neg\", (a, _) => a, [x, y] => x, _ => \"other\" })"]
+    );
+}
+
+/// Loops, typed bindings and every kind of assignment are statements of their own
+#[test]
+fn statements_are_parsed() {
+    assert_eq!(
+        items("@ i < 10 { i += 1 }\n@ { $(i) }\n(a, b): (int, int) := (1, 2)\nc <- 5\nd.e = 3\n"),
+        [
+            "@ (i < 10) { i += 1 }",
+            "@ { $(i) }",
+            "(a, b): (int, int) := (1, 2)",
+            "c <- 5",
+            "d.e = 3",
+        ]
+    );
+}
+
+/// Only names, fields and indexes can be assigned to
+#[test]
+fn invalid_assignment_targets_are_reported() {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let tokens = scan("1 = 2\n", &"test.rum".to_owned(), &mut diagnostics).unwrap();
+    let ast: Box<Ast> = Ast::new(tokens, &mut diagnostics);
+    assert_eq!(ast.items[0].to_string(), "<error>");
+
+    let problems: Vec<&Diagnostic> = diagnostics.iter().collect();
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0].code.as_deref(), Some("R0108"));
+    assert_eq!(problems[0].span.to_string(), "1:1-1:2");
+}
+
+/// A missing token is reported as it would be spelled in the source code
+#[test]
+fn missing_tokens_are_reported_by_symbol() {
+    for (source_code, message) in [
+        ("x := a[1\n", "Expected []] but reached end of file"),
+        ("y := 1 ? { 1 2 }\n", "Expected [=>] but found [2]"),
+        ("f := (1) -> { }\n", "Expected a name but found [1]"),
+    ] {
+        let mut diagnostics: Diagnostics = Diagnostics::new();
+        let tokens = scan(source_code, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+        Ast::new(tokens, &mut diagnostics);
+
+        let problems: Vec<&Diagnostic> = diagnostics.iter().collect();
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].code.as_deref(), Some("R0101"));
+        assert_eq!(problems[0].message, message);
+    }
+}
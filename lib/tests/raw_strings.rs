@@ -0,0 +1,68 @@
+use std::borrow::Cow;
+
+use rumil_parser::{
+    diagnostic::{Diagnostic, Diagnostics},
+    lexer::scan,
+    token::{LiteralValue, Token, TokenType},
+};
+
+/// Utility function to scan source code, returning its tokens and every problem found
+fn scan_all(source_code: &str) -> (Vec<Token<'_>>, Diagnostics) {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let tokens: Vec<Token> = scan(source_code, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+    (tokens, diagnostics)
+}
+
+/// Utility function to get the contents of a single raw string
+fn contents(source_code: &str) -> Cow<'_, str> {
+    let (mut tokens, diagnostics) = scan_all(source_code);
+    assert!(diagnostics.is_empty());
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0].token_type, TokenType::String);
+    assert_eq!(tokens[0].value, source_code);
+    match tokens.remove(0).literal {
+        Some(LiteralValue::Str(contents)) => contents,
+        _ => panic!("Expected a string literal"),
+    }
+}
+
+/// Backslashes in raw strings are just backslashes, and the contents are borrowed from the source code
+#[test]
+fn raw_strings_have_no_escapes() {
+    let path: Cow<str> = contents(r#"r"C:\a\n""#);
+    assert_eq!(path, r"C:\a\n");
+    assert!(matches!(path, Cow::Borrowed(_)));
+    assert_eq!(contents("r\"multi\nline\""), "multi\nline");
+    assert_eq!(contents(r#"r"""#), "");
+}
+
+/// A raw string ends at the first quote followed by as many #s as it started with
+#[test]
+fn hashes_let_raw_strings_hold_quotes() {
+    assert_eq!(contents(r##"r#"say "hi""#"##), r#"say "hi""#);
+    assert_eq!(contents(r###"r##"a "# b"##"###), r##"a "# b"##);
+}
+
+/// An r that isn't followed by a quote, after any #s, is an identifier
+#[test]
+fn r_alone_is_an_identifier() {
+    let (tokens, diagnostics) = scan_all("r x rx r#x");
+    assert!(diagnostics.is_empty());
+    let scanned: Vec<&str> = tokens.iter().map(|tk| tk.value).collect();
+    assert_eq!(scanned, ["r", "x", "rx", "r", "#", "x"]);
+    assert_eq!(tokens[0].token_type, TokenType::Identifier);
+    assert_eq!(tokens[4].token_type, TokenType::Hash);
+}
+
+/// An unclosed raw string is an error that says which closing quote is missing
+#[test]
+fn unclosed_raw_strings_are_reported() {
+    let (tokens, diagnostics) = scan_all(r##"r#"open""##);
+    assert_eq!(tokens[0].token_type, TokenType::Error);
+    let problems: Vec<&Diagnostic> = diagnostics.iter().collect();
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0].code.as_deref(), Some("R0001"));
+    assert_eq!(problems[0].message, "Unclosed raw string [r#\"]");
+    assert_eq!(problems[0].help.as_deref(), Some("Add a closing [\"#]"));
+    assert_eq!(problems[0].span.to_string(), "1:1-1:9");
+}
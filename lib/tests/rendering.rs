@@ -0,0 +1,100 @@
+use rumil_parser::{
+    ast::Ast,
+    diagnostic::{Diagnostic, Diagnostics},
+    lexer::scan,
+};
+
+/// Utility function to scan and parse source code, rendering every problem found without colors
+fn render_all(source_code: &str) -> Vec<String> {
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let tokens = scan(source_code, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+    Ast::new(tokens, &mut diagnostics);
+    diagnostics
+        .iter()
+        .map(|d| d.render(source_code, "test.rum", false))
+        .collect()
+}
+
+/// Diagnostics show their code, location and source line, with a caret under the problem
+#[test]
+fn diagnostics_underline_the_problem() {
+    let rendered: Vec<String> = render_all("x := 1 +* 2\n");
+    assert_eq!(
+        rendered[0],
+        "\
+error[R0102]: Expected an expression but found [*]
+ --> test.rum:1:9
+  |
+1 | x := 1 +* 2
+  |         ^
+"
+    );
+}
+
+/// Labels are underlined with dashes and their message, and tabs are expanded so carets line up
+#[test]
+fn labels_and_tabs_line_up() {
+    let rendered: Vec<String> = render_all("f := (x) -> {\n\ty := x\n");
+    assert_eq!(
+        rendered[0],
+        "\
+error[R0105]: Unclosed block, expected [}] but reached end of file
+ --> test.rum:2:8
+  |
+1 | f := (x) -> {
+  |             - Block opened here
+2 |     y := x
+  |           ^
+"
+    );
+}
+
+/// Spans over many lines only show their first two and last lines, and help comes at the end
+#[test]
+fn long_spans_are_elided() {
+    let rendered: Vec<String> = render_all("x := \"a\n\n\n\n\n\nb\n");
+    assert_eq!(
+        rendered[0],
+        "\
+error[R0001]: Unclosed quote [\"]
+ --> test.rum:1:6
+  |
+1 | x := \"a
+  |      ^^
+2 |\x20
+  | ^
+...
+7 | b
+  | ^
+  = help: Add a closing [\"]
+"
+    );
+}
+
+/// Colors are only added when asked for, and the text is the same either way
+#[test]
+fn colors_are_optional() {
+    colored::control::set_override(true);
+    let source_code: &str = "pаth := 1\n";
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    scan(source_code, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+    let warning: &Diagnostic = diagnostics.iter().next().unwrap();
+
+    let plain: String = warning.render(source_code, "test.rum", false);
+    let colored: String = warning.render(source_code, "test.rum", true);
+    assert!(plain.starts_with("warning[R0012]:") && !plain.contains('\x1b'));
+    assert!(colored.contains('\x1b'));
+
+    // Strip the escape codes back out of the colored text
+    let mut stripped: String = String::new();
+    let mut in_escape: bool = false;
+    for c in colored.chars() {
+        match c {
+            '\x1b' => in_escape = true,
+            'm' if in_escape => in_escape = false,
+            _ if !in_escape => stripped.push(c),
+            _ => {}
+        }
+    }
+    assert_eq!(stripped, plain);
+}
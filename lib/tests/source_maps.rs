@@ -0,0 +1,114 @@
+use rumil_parser::{
+    ast::Ast,
+    diagnostic::{Diagnostic, Diagnostics},
+    dialect::Dialect,
+    lexer::scan,
+    span::{Position, Span},
+    token::Token,
+    transpile::{SourceMap, Transpiled, transpile},
+};
+
+/// A Spanish-flavored dialect, with keywords and symbols of different lengths to the operators they stand for
+const ESPAÑOL: &str = r#"
+name = "Español"
+
+[keywords]
+imprimir = "$"
+no = "!"
+
+[operators]
+"←" = ":="
+"#;
+
+/// Utility function to transpile dialect source code, then scan and parse the Rumil it gives, returning
+/// the transpiled code and every problem found in it, taken back to the dialect source code
+fn check(source_code: &str) -> (Transpiled, Diagnostics) {
+    let dialect: Dialect = Dialect::from_toml(ESPAÑOL).unwrap();
+    let transpiled: Transpiled = transpile(source_code, &dialect);
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let tokens: Vec<Token> =
+        scan(&transpiled.code, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+    Ast::new(tokens, &mut diagnostics);
+    transpiled.source_map.remap(&mut diagnostics);
+    (transpiled, diagnostics)
+}
+
+/// Utility function to make a span on a single line
+fn span(line: i32, start: i32, end: i32, offset: usize) -> Span {
+    Span::new(
+        Position::new(line, start, offset),
+        Position::new(line, end, offset + (end - start) as usize),
+    )
+}
+
+/// Dialect keywords and symbols are rewritten as Rumil, while comments, formatting and form strings
+/// are copied over, with spaces added only where the rewritten tokens would run together
+#[test]
+fn transpiling_rewrites_dialect_spellings() {
+    for (source_code, code) in [
+        ("x ← 1 ; nota\nimprimir x\n", "x := 1 ; nota\n$ x\n"),
+        ("z ← `a {imprimir}`\n", "z := `a {$}`\n"),
+        ("x←-1\n", "x:=-1\n"),
+        ("x ← no=1\n", "x := ! =1\n"),
+    ] {
+        assert_eq!(check(source_code).0.code, code);
+    }
+}
+
+/// Problems found in the transpiled code point at what was written in the dialect source code, labels included
+#[test]
+fn diagnostics_are_remapped() {
+    for (source_code, code, span, label) in [
+        ("imprimir 'ab'\n", "R0002", "1:10-1:14", None),
+        ("imprimir 12३\n", "R0014", "1:12-1:13", Some("1:10-1:11")),
+        ("x ← no=1\n", "R0102", "1:7-1:8", None),
+        ("x ←\nimprimir (\n", "R0102", "2:11-2:11", None),
+    ] {
+        let (_, diagnostics) = check(source_code);
+        let problems: Vec<&Diagnostic> = diagnostics.iter().collect();
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].code.as_deref(), Some(code));
+        assert_eq!(problems[0].span.to_string(), span);
+        assert_eq!(
+            problems[0].labels.first().map(|l| l.span.to_string()),
+            label.map(str::to_owned)
+        );
+    }
+}
+
+/// Spans in copied text move by however much the text before them changed, and spans in rewritten
+/// text cover the whole keyword or symbol it was rewritten from
+#[test]
+fn spans_map_to_the_dialect_source_code() {
+    let (transpiled, _) = check("x ← 1 ; nota\nimprimir x\n");
+    let source_map: &SourceMap = &transpiled.source_map;
+    let original = |generated: Span| -> String { source_map.original_span(generated).to_string() };
+
+    // x := 1 ; nota
+    // $ x
+    assert_eq!(original(span(1, 1, 2, 0)), "1:1-1:2");
+    assert_eq!(original(span(1, 3, 5, 2)), "1:3-1:4");
+    assert_eq!(original(span(1, 4, 5, 3)), "1:3-1:4");
+    assert_eq!(original(span(1, 6, 7, 5)), "1:5-1:6");
+    assert_eq!(original(span(2, 1, 2, 14)), "2:1-2:9");
+    assert_eq!(original(span(2, 3, 4, 16)), "2:10-2:11");
+    assert_eq!(source_map.original_span(span(2, 3, 4, 16)).start.offset, 24);
+}
+
+/// Source maps can be written out as JSON and read back in
+#[test]
+fn source_maps_round_trip_through_json() {
+    let (transpiled, _) = check("x ← 1\nimprimir x\n");
+    let json: String = transpiled.source_map.to_json();
+    let read: SourceMap = SourceMap::from_json(&json).unwrap();
+    assert_eq!(read.to_json(), json);
+    assert_eq!(read.mappings.len(), transpiled.source_map.mappings.len());
+    assert_eq!(read.original_span(span(2, 1, 2, 7)).to_string(), "2:1-2:9");
+
+    assert!(
+        SourceMap::from_json("{ \"mappings\": 1 }")
+            .err()
+            .unwrap()
+            .starts_with("Invalid source map: ")
+    );
+}
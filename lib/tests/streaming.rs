@@ -0,0 +1,75 @@
+use rumil_parser::{
+    diagnostic::{Diagnostic, Diagnostics},
+    lexer::{Lexer, scan},
+    token::{Token, TokenType},
+};
+
+/// Utility function to describe tokens as their values and spans
+fn describe(tokens: &[Token]) -> Vec<String> {
+    tokens
+        .iter()
+        .map(|tk| format!("{} {} {}", tk.token_type, tk.value, tk.span))
+        .collect()
+}
+
+/// Collecting a Lexer gives the same tokens and problems as scanning everything up front
+#[test]
+fn lexers_match_scan() {
+    let source_code: &str = "x := 1 ; one\ny := `a {x}` + \"open\nz := 'ab'\n";
+    let mut diagnostics: Diagnostics = Diagnostics::new();
+    let scanned: Vec<Token> = scan(source_code, &"test.rum".to_owned(), &mut diagnostics).unwrap();
+
+    let mut lexer: Lexer = Lexer::new(source_code);
+    let streamed: Vec<Token> = lexer.by_ref().collect();
+    assert_eq!(describe(&streamed), describe(&scanned));
+
+    let problems = |diagnostics: &Diagnostics| -> Vec<String> {
+        diagnostics
+            .iter()
+            .map(|d| format!("{} {}", d.message, d.span))
+            .collect()
+    };
+    assert_eq!(problems(&lexer.take_diagnostics()), problems(&diagnostics));
+}
+
+/// Peeking looks any number of tokens ahead without taking them
+#[test]
+fn peeking_does_not_consume() {
+    let mut lexer: Lexer = Lexer::new("a + b");
+    assert_eq!(lexer.peek_nth(2).map(|tk| tk.value), Some("b"));
+    assert_eq!(lexer.peek().map(|tk| tk.value), Some("a"));
+    assert!(lexer.peek_nth(3).is_none());
+
+    let values: Vec<&str> = lexer.by_ref().map(|tk| tk.value).collect();
+    assert_eq!(values, ["a", "+", "b"]);
+    assert!(lexer.peek().is_none());
+}
+
+/// Tokens are only scanned when they're asked for, so stopping early skips the problems further on,
+/// while problems in tokens that were only peeked at are still reported
+#[test]
+fn lexers_scan_on_demand() {
+    let mut lexer: Lexer = Lexer::new("x := 1\ny := 'ab'\nz := \"open\n");
+    let first: Vec<Token> = lexer.by_ref().take(3).collect();
+    assert_eq!(first.len(), 3);
+    assert!(lexer.take_diagnostics().is_empty());
+
+    assert_eq!(lexer.peek_nth(2).unwrap().token_type, TokenType::Error);
+    let diagnostics: Diagnostics = lexer.take_diagnostics();
+    let problems: Vec<&Diagnostic> = diagnostics.iter().collect();
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0].span.to_string(), "2:6-2:10");
+}
+
+/// Only lossless Lexers hand out the EOF token, since it holds the trivia after the last token
+#[test]
+fn lossless_lexers_end_with_eof() {
+    let plain: Vec<Token> = Lexer::new("x ; done\n").collect();
+    assert_ne!(plain.last().unwrap().token_type, TokenType::EOF);
+
+    let lossless: Vec<Token> = Lexer::lossless("x ; done\n").collect();
+    assert_eq!(lossless.len(), 2);
+    assert_eq!(lossless[1].token_type, TokenType::EOF);
+    assert_eq!(lossless[1].leading.len(), 0);
+    assert_eq!(lossless[0].full_text(), "x ; done\n");
+}
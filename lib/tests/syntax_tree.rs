@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use rumil_parser::{
+    ast::{ExprKind, ItemKind, StmtKind},
+    syntax::{
+        GreenElement, GreenNode, GreenToken, SyntaxKind, SyntaxNode, SyntaxToken, SyntaxTree,
+        TokenKind,
+    },
+    token::TokenType,
+};
+
+/// Source code with comments, odd formatting and syntax errors in it
+const SOURCE: &str = "\
+;; Doubles a number
+double := (n: int) -> int {
+    ;* the only
+       statement *;
+    -> n   *   2 ; twice
+}
+
+x := double( -1 ) ; call it
+\tnames: [str] := [\"a\", `b {x}`]
+k := x ? { 1 => )
+bad := \"unclosed
+";
+
+/// The syntax tree holds every char of the source code, even where it has syntax errors
+#[test]
+fn trees_are_lossless() {
+    let tree: SyntaxTree = SyntaxTree::parse(SOURCE);
+    assert_eq!(tree.text(), SOURCE);
+    let tokens: String = tree.root().tokens().iter().map(SyntaxToken::text).collect();
+    assert_eq!(tokens, SOURCE);
+    assert!(tree.diagnostics().has_errors());
+
+    let kinds: Vec<SyntaxKind> = tree.root().children().map(|node| node.kind()).collect();
+    assert!(kinds.contains(&SyntaxKind::Binding));
+    assert_eq!(kinds.len(), tree.ast().items.len());
+}
+
+/// Nodes start and end with tokens the parser reads, and trivia between them goes to their parent
+#[test]
+fn trivia_stays_outside_nodes() {
+    let tree: SyntaxTree = SyntaxTree::parse("x := 1 ; one\n");
+    assert_eq!(
+        tree.root().dump(),
+        "\
+Program@0..13
+  Binding@0..6
+    NamePattern@0..1
+      Identifier \"x\"
+    Whitespace \" \"
+    ColonEquals \":=\"
+    Whitespace \" \"
+    Literal@5..6
+      Int \"1\"
+  Whitespace \" \"
+  Comment \"; one\"
+  Newline \"\\n\"
+"
+    );
+}
+
+/// Every Ast node covers the span of the syntax node it was parsed from
+#[test]
+fn ast_nodes_map_to_syntax_nodes() {
+    let tree: SyntaxTree = SyntaxTree::parse(SOURCE);
+    for item in &tree.ast().items {
+        assert_eq!(tree.syntax(item.span).range(), item.span.range());
+    }
+
+    assert_eq!(tree.ast().items[0].docs, ["Doubles a number"]);
+    let ItemKind::Function { function, .. } = &tree.ast().items[0].kind else {
+        panic!("Expected a function");
+    };
+    let StmtKind::Return(Some(value)) = &function.body.statements[0].kind else {
+        panic!("Expected a return");
+    };
+    let node: SyntaxNode = tree.syntax(value.span);
+    assert_eq!(node.kind(), SyntaxKind::Binary);
+    assert_eq!(node.text(), "n   *   2");
+    assert!(
+        node.parent()
+            .is_some_and(|parent| parent.kind() == SyntaxKind::Return)
+    );
+
+    let ItemKind::Binding(binding) = &tree.ast().items[1].kind else {
+        panic!("Expected a binding");
+    };
+    let ExprKind::Call { args, .. } = &binding.value.kind else {
+        panic!("Expected a call");
+    };
+    assert_eq!(tree.syntax(args[0].span).kind(), SyntaxKind::Unary);
+}
+
+/// Editing a token gives a new tree that keeps the rest of the source code as it was, sharing every
+/// node the edit didn't touch with the original tree
+#[test]
+fn edits_keep_formatting_and_share_nodes() {
+    let tree: SyntaxTree = SyntaxTree::parse(SOURCE);
+    let root: SyntaxNode = tree.root();
+
+    // Rename the function everywhere it's used
+    let mut renamed: SyntaxNode = root.clone();
+    while let Some(token) = renamed
+        .tokens()
+        .into_iter()
+        .find(|token| token.text() == "double")
+    {
+        renamed = token.replace_with(GreenToken::new(
+            TokenKind::Token(TokenType::Identifier),
+            "twice".to_owned(),
+        ));
+    }
+    assert_eq!(renamed.text(), SOURCE.replace("double", "twice"));
+    assert_eq!(root.text(), SOURCE);
+
+    // The last item is untouched, so it's the very same node in both trees
+    let last = |node: &SyntaxNode| -> Arc<GreenNode> {
+        node.children()
+            .last()
+            .map(|node| node.green().clone())
+            .unwrap()
+    };
+    assert!(Arc::ptr_eq(&last(&root), &last(&renamed)));
+
+    // Replace a whole node
+    let body: SyntaxNode =
+        root.covering_node(SOURCE.find("n   *").unwrap()..SOURCE.find("; twice").unwrap() - 1);
+    assert_eq!(body.kind(), SyntaxKind::Binary);
+    let replaced: SyntaxNode = body.replace_with(GreenNode::new(
+        SyntaxKind::Name,
+        vec![GreenElement::Token(Arc::new(GreenToken::new(
+            TokenKind::Token(TokenType::Identifier),
+            "n".to_owned(),
+        )))],
+    ));
+    assert!(replaced.text().contains("    -> n ; twice\n"));
+}
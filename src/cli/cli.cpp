@@ -1,6 +1,8 @@
 #include <functional>
 #include <iostream>
 
+#include <rumil.h>
+
 #include "cli.hpp"
 #include "../context/context.hpp"
 
@@ -10,6 +12,8 @@ int cmd_version(std::vector<std::string> &);
 int cmd_run(std::vector<std::string> &);
 int cmd_debug(std::vector<std::string> &);
 int cmd_build(std::vector<std::string> &);
+int cmd_explain(std::vector<std::string> &);
+int cmd_transpile(std::vector<std::string> &);
 
 // A description of a CLI command
 struct Command
@@ -40,7 +44,9 @@ std::vector<Command> commands{
     {"version", "Prints the current Rumil version to the command line", cmd_version, false},
     {"run", "Executes the given source code", cmd_run, true},
     {"debug", "Executes the given source code with runtime logs", cmd_debug, true},
-    {"build", "Builds the given source code into an executable binary", cmd_build, true}};
+    {"build", "Builds the given source code into an executable binary", cmd_build, true},
+    {"explain", "Prints a detailed explanation of an error code", cmd_explain, false},
+    {"transpile", "Transpiles the given source code from a dialect into canonical Rumil", cmd_transpile, true}};
 
 // Handle command line arguments to Rumil
 int parse_args(std::vector<std::string> &args)
@@ -103,8 +109,12 @@ int cmd_help(std::vector<std::string> &)
         msg += "|";
         msg += command.identifier[0];
 
-        if (command.requires_src)
+        if (command.identifier == "transpile")
+            msg += " source_file.rum [output_file.rum]";
+        else if (command.requires_src)
             msg += " source_file.rum";
+        else if (command.identifier == "explain")
+            msg += " error_code";
 
         msg += "\n\n";
     }
@@ -120,6 +130,28 @@ int cmd_version(std::vector<std::string> &)
     return 0;
 }
 
+// Print the long-form explanation of an error code reported by the parser
+int cmd_explain(std::vector<std::string> &args)
+{
+    if (args.empty())
+    {
+        std::cout << "Please provide an error code to explain, such as R0001\n";
+        return 1;
+    }
+
+    return explain_code(args[0].c_str()) ? 0 : 1;
+}
+
+// Transpile source code written in a dialect into canonical Rumil, with a source map next to it.
+// The output goes to source_file.canonical.rum unless another file is given
+int cmd_transpile(std::vector<std::string> &args)
+{
+    std::string src{args[0]};
+    std::string out{args.size() > 1 ? args[1] : src.substr(0, src.size() - 4) + ".canonical.rum"};
+
+    return transpile_file(src.c_str(), out.c_str()) ? 0 : 1;
+}
+
 // ===================
 // Invocation Handling
 // ===================